@@ -20,6 +20,12 @@ pub trait AllowIf: Sized {
   fn allow_if_not_mut<F: FnOnce(&mut Self::Accepted) -> bool> (self, f: F) -> Option<Self::Accepted> {
     self.allow_if_mut(|x| !f(x))
   }
+
+  /// Check an Option value against some condition, keeping the value whether or not it is met
+  /// 
+  /// Gives `Some(Ok(value))` if the condition is met, `Some(Err(value))` if it is not,
+  /// so that a rejected value can be reported, or None if there was no value to check
+  fn allow_if_or_reject<F: FnOnce(&Self::Accepted) -> bool> (self, f: F) -> Option<Result<Self::Accepted, Self::Accepted>>;
 }
 
 impl<T> AllowIf for Option<T> {
@@ -40,4 +46,22 @@ impl<T> AllowIf for Option<T> {
       None => None
     }
   }
+
+  #[inline]
+  fn allow_if_or_reject<F: FnOnce(&Self::Accepted) -> bool> (self, f: F) -> Option<Result<T, T>> {
+    self.map(|x| if f(&x) { Ok(x) } else { Err(x) })
+  }
+}
+
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn allow_if_or_reject () {
+    assert_eq!(Some(4).allow_if_or_reject(|x| x % 2 == 0), Some(Ok(4)));
+    assert_eq!(Some(5).allow_if_or_reject(|x| x % 2 == 0), Some(Err(5)));
+    assert_eq!(None::<i32>.allow_if_or_reject(|_| unreachable!()), None);
+  }
 }
\ No newline at end of file
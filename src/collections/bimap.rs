@@ -196,6 +196,33 @@ impl<K: PartialEq + Hash, V: PartialEq + Hash> BiMap<K, V> {
     self.values.is_empty()
   }
 
+  /// Get the number of (key, value) pairs a BiMap can hold without reallocating
+  #[inline]
+  pub fn capacity (&self) -> usize {
+    self.keys.capacity()
+      .min(self.values.capacity())
+      .min(self.key_hashes.capacity())
+      .min(self.value_hashes.capacity())
+  }
+
+  /// Reserve capacity for at least `additional` more (key, value) pairs in a BiMap
+  #[inline]
+  pub fn reserve (&mut self, additional: usize) {
+    self.keys.reserve(additional);
+    self.values.reserve(additional);
+    self.key_hashes.reserve(additional);
+    self.value_hashes.reserve(additional);
+  }
+
+  /// Shrink the capacity of a BiMap as much as possible
+  #[inline]
+  pub fn shrink_to_fit (&mut self) {
+    self.keys.shrink_to_fit();
+    self.values.shrink_to_fit();
+    self.key_hashes.shrink_to_fit();
+    self.value_hashes.shrink_to_fit();
+  }
+
 
   /// Get an immutable reference to a value associated with a given key in a BiMap,
   /// if it contains a pair with a matching key
@@ -223,6 +250,17 @@ impl<K: PartialEq + Hash, V: PartialEq + Hash> BiMap<K, V> {
     }
   }
 
+  /// Get an immutable reference to a value associated with a given key in a BiMap
+  /// 
+  /// # Panics
+  /// Panics with the given message if the BiMap does not contain a pair with a matching key
+  #[inline]
+  pub fn expect_value<EqK: Hash + ?Sized> (&self, key: &EqK, msg: &str) -> &V
+  where K: PartialEq<EqK>
+  {
+    self.find_value(key).expect(msg)
+  }
+
 
   /// Get an immutable reference to a key associated with a given value in a BiMap,
   /// if it contains a pair with a matching value
@@ -291,6 +329,19 @@ impl<K: PartialEq + Hash, V: PartialEq + Hash> BiMap<K, V> {
     }
   }
 
+  /// Get a copy of the key and an immutable reference to the value of a (key, value) pair in a BiMap by index
+  /// 
+  /// A range check is performed on the index
+  /// 
+  /// Note that the BiMap type does not necessarily preserve its order,
+  /// so index-based referencing is temporaly unstable
+  #[inline]
+  pub fn get_pair_copied_key (&self, idx: usize) -> Option<(K, &V)>
+  where K: Copy
+  {
+    self.get_pair(idx).map(|(key, value)| (*key, value))
+  }
+
   /// Get a mutable references to a (key, value) pair in a BiMap by index
   /// 
   /// A range check is performed on the index
@@ -458,6 +509,37 @@ impl<K: PartialEq + Hash, V: PartialEq + Hash> BiMap<K, V> {
     }
   }
 
+  /// Remove all (key, value) pairs from a BiMap for which a predicate returns true,
+  /// and get the removed pairs
+  /// 
+  /// The predicate receives each key and value, in pair order
+  /// 
+  /// Does not preserve order
+  pub fn drain_filter<F: FnMut(&K, &V) -> bool> (&mut self, mut f: F) -> Vec<(K, V)> {
+    let mut removed = Vec::new();
+    let mut idx = 0;
+
+    while idx < self.len() {
+      if f(unsafe { self.keys.get_unchecked(idx) }, unsafe { self.values.get_unchecked(idx) }) {
+        // remove_by_index swaps the last pair into idx, so it is visited next
+        removed.push(self.remove_by_index(idx).unwrap());
+      } else {
+        idx += 1;
+      }
+    }
+
+    removed
+  }
+
+  /// Remove all (key, value) pairs from a BiMap
+  #[inline]
+  pub fn clear (&mut self) {
+    self.keys.clear();
+    self.values.clear();
+    self.key_hashes.clear();
+    self.value_hashes.clear();
+  }
+
 
   /// Get an immutable slice of the keys of a BiMap
   #[inline]
@@ -483,6 +565,14 @@ impl<K: PartialEq + Hash, V: PartialEq + Hash> BiMap<K, V> {
     self.values.as_mut_slice()
   }
 
+  /// Get immutable slices of both the keys and values of a BiMap
+  /// 
+  /// The slices are aligned, so the key and value at a given index form a pair
+  #[inline]
+  pub fn keys_values (&self) -> (&[K], &[V]) {
+    (self.keys.as_slice(), self.values.as_slice())
+  }
+
   /// Get an immutable iterator over the keys of a BiMap
   #[inline]
   pub fn key_iter (&self) -> SliceIter<K> {
@@ -512,16 +602,36 @@ impl<K: PartialEq + Hash, V: PartialEq + Hash> BiMap<K, V> {
 
   /// Get an immutable iterator over the (key, value) pairs of a BiMap
   #[inline]
-  pub fn iter (&self) -> PairIter<K, V> {
+  pub fn iter (&self) -> PairIter<'_, K, V> {
     PairIter::new(self)
   }
 
   /// Get a mutable iterator over the (key, value) pairs of a BiMap
   #[inline]
-  pub fn iter_mut (&mut self) -> PairIterMut<K, V> {
+  pub fn iter_mut (&mut self) -> PairIterMut<'_, K, V> {
     PairIterMut::new(self)
   }
 
+  /// Get an immutable iterator over the (value, key) pairs of a BiMap
+  /// 
+  /// This yields the same pairs as `iter`, in the same order, but value first
+  #[inline]
+  pub fn iter_rev (&self) -> impl Iterator<Item = (&V, &K)> + '_ {
+    self.values.iter().zip(self.keys.iter())
+  }
+
+  /// Consume a BiMap and get an iterator over its keys
+  #[inline]
+  pub fn into_keys (self) -> VecIntoIter<K> {
+    self.keys.into_iter()
+  }
+
+  /// Consume a BiMap and get an iterator over its values
+  #[inline]
+  pub fn into_values (self) -> VecIntoIter<V> {
+    self.values.into_iter()
+  }
+
 
   /// Move the (key, value) pairs of another BiMap into a BiMap
   /// 
@@ -568,6 +678,40 @@ impl<K: PartialEq + Hash, V: PartialEq + Hash> BiMap<K, V> {
       self.insert_at_key(key, value);
     }
   }
+
+  /// Move the (key, value) pairs of another BiMap into a BiMap
+  /// 
+  /// Pairs whose key already exists in the BiMap are combined by calling `resolve`
+  /// with the existing value and the incoming value, after which the value is rehashed
+  /// 
+  /// Pairs whose key does not exist, but whose value is already bound to a different key,
+  /// are skipped (and dropped) to preserve the bijection;
+  /// likewise `resolve` must not produce a value already bound to a different key
+  /// 
+  /// Consumes the other BiMap
+  pub fn merge_with<F: FnMut(&mut V, V)> (&mut self, other: Self, mut resolve: F) {
+    for (key, value) in other {
+      let key_hash = Self::hash_key(&key);
+
+      if let Some(idx) = self.index_of_hashed_key(key_hash, &key) {
+        let own_value = unsafe { self.values.get_unchecked_mut(idx) };
+
+        resolve(own_value, value);
+
+        let value_hash = Self::hash_value(own_value);
+        *unsafe { self.value_hashes.get_unchecked_mut(idx) } = value_hash;
+      } else {
+        let value_hash = Self::hash_value(&value);
+
+        if self.index_of_hashed_value(value_hash, &value).is_some() { continue }
+
+        self.keys.push(key);
+        self.values.push(value);
+        self.key_hashes.push(key_hash);
+        self.value_hashes.push(value_hash);
+      }
+    }
+  }
 }
 
 
@@ -590,6 +734,14 @@ where K: PartialEq<EqK>
   }
 }
 
+/// BiMaps are equal if they contain the same (key, value) pairs, regardless of order
+impl<K: PartialEq + Hash, V: PartialEq + Hash> PartialEq for BiMap<K, V> {
+  fn eq (&self, other: &Self) -> bool {
+    self.len() == other.len()
+    && self.iter().all(|(key, value)| other.find_value(key) == Some(value))
+  }
+}
+
 
 /// An iterator over (Key, Value) for a BiMap
 pub struct PairIter<'a, K: PartialEq + Hash + 'a, V: PartialEq + Hash + 'a> {
@@ -720,4 +872,150 @@ impl<K: PartialEq + Hash, V: PartialEq + Hash> FromIterator<(K, V)> for BiMap<K,
 
     map
   }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn keys_values () {
+    let map: BiMap<&str, i32> = vec![("a", 1), ("b", 2), ("c", 3)].into_iter().collect();
+
+    let (keys, values) = map.keys_values();
+    assert_eq!(keys.len(), values.len());
+
+    for i in 0..keys.len() {
+      assert_eq!(map.find_value(&keys[i]), Some(&values[i]));
+      assert_eq!(map.find_key(&values[i]), Some(&keys[i]));
+    }
+  }
+
+  #[test]
+  fn eq_ignores_order () {
+    let a: BiMap<&str, i32> = vec![("a", 1), ("b", 2), ("c", 3)].into_iter().collect();
+    let mut b: BiMap<&str, i32> = vec![("b", 2), ("c", 3), ("a", 1)].into_iter().collect();
+
+    assert_eq!(a, b);
+
+    b.insert_at_key("b", 4);
+
+    assert_ne!(a, b);
+  }
+
+  #[test]
+  fn reserve () {
+    let mut map: BiMap<usize, usize> = BiMap::with_capacity(0);
+
+    map.reserve(100);
+
+    let cap = map.capacity();
+    assert!(cap >= 100);
+
+    for i in 0..100 {
+      map.insert_at_key(i, i * 2);
+    }
+
+    assert_eq!(map.capacity(), cap);
+
+    map.remove_by_key(&0);
+    map.shrink_to_fit();
+
+    assert!(map.capacity() >= 99 && map.capacity() <= cap);
+  }
+
+  #[test]
+  fn merge_with () {
+    let mut a: BiMap<&str, i32> = vec![("x", 1), ("y", 2)].into_iter().collect();
+    let b: BiMap<&str, i32> = vec![("y", 10), ("z", 20), ("w", 1)].into_iter().collect();
+
+    a.merge_with(b, |existing, incoming| *existing += incoming);
+
+    assert_eq!(a.len(), 3);
+    assert_eq!(a[&"y"], 12);
+    assert_eq!(a[&"z"], 20);
+    assert_eq!(a.find_key(&12), Some(&"y"));
+    assert_eq!(a.find_key(&1), Some(&"x"));
+    assert!(!a.contains_key(&"w"));
+  }
+
+  #[test]
+  fn get_pair_copied_key () {
+    let map: BiMap<u32, &str> = vec![(1, "a"), (2, "b")].into_iter().collect();
+
+    for idx in 0..map.len() {
+      let (key, value) = map.get_pair_copied_key(idx).unwrap();
+      let (own_key, own_value) = map.get_pair(idx).unwrap();
+
+      assert_eq!(key, *own_key);
+      assert_eq!(value, own_value);
+    }
+
+    assert!(map.get_pair_copied_key(2).is_none());
+  }
+
+  #[test]
+  #[should_panic(expected = "no entry for missing")]
+  fn expect_value () {
+    let map: BiMap<&str, i32> = vec![("a", 1)].into_iter().collect();
+
+    assert_eq!(*map.expect_value(&"a", "no entry for a"), 1);
+
+    map.expect_value(&"missing", "no entry for missing");
+  }
+
+  #[test]
+  fn drain_filter () {
+    let mut map: BiMap<i32, String> = (0..8).map(|i| (i, format!("v{}", i))).collect();
+
+    let mut removed = map.drain_filter(|key, value| key % 2 == 1 || value == "v4");
+    removed.sort_unstable();
+
+    assert_eq!(removed, vec![(1, "v1".to_owned()), (3, "v3".to_owned()), (4, "v4".to_owned()), (5, "v5".to_owned()), (7, "v7".to_owned())]);
+    assert_eq!(map.len(), 3);
+
+    for &key in &[0, 2, 6] {
+      let value = format!("v{}", key);
+
+      assert_eq!(map.find_value(&key), Some(&value));
+      assert_eq!(map.find_key(&value), Some(&key));
+    }
+
+    assert!(!map.contains_key(&4) && !map.contains_value(&"v1".to_owned()));
+  }
+
+  #[test]
+  fn iter_rev () {
+    let map: BiMap<&str, i32> = vec![("a", 1), ("b", 2), ("c", 3)].into_iter().collect();
+
+    let flipped: Vec<(&i32, &&str)> = map.iter().map(|(k, v)| (v, k)).collect();
+
+    assert_eq!(map.iter_rev().collect::<Vec<_>>(), flipped);
+  }
+
+  #[test]
+  fn into_keys_values () {
+    let map: BiMap<&str, i32> = vec![("a", 1), ("b", 2), ("c", 3)].into_iter().collect();
+
+    assert_eq!(map.clone().into_keys().collect::<Vec<_>>(), vec!["a", "b", "c"]);
+    assert_eq!(map.into_values().collect::<Vec<_>>(), vec![1, 2, 3]);
+  }
+
+  #[test]
+  fn clear () {
+    let mut map: BiMap<&str, i32> = vec![("a", 1), ("b", 2), ("c", 3)].into_iter().collect();
+
+    map.clear();
+
+    assert!(map.is_empty());
+    assert!(!map.contains_key(&"a"));
+    assert!(!map.contains_value(&2));
+
+    map.insert_at_key("c", 4);
+
+    assert_eq!(map.len(), 1);
+    assert_eq!(map.find_value(&"c"), Some(&4));
+    assert_eq!(map.find_key(&4), Some(&"c"));
+  }
 }
\ No newline at end of file
@@ -18,6 +18,7 @@ pub use std::{
   marker::PhantomData,
   iter::FromIterator,
   vec::IntoIter as VecIntoIter,
+  borrow::Borrow,
 };
 
 
@@ -29,26 +30,42 @@ pub use std::{
 /// Key types must implement PartialEq, Clone, and Hash
 /// 
 /// Value types must implement PartialEq
+/// 
+/// Every insertion, removal, or key replacement increments the Map's `version`,
+/// which can be compared against a previously observed version to cheaply detect changes
 #[derive(Debug, Clone)]
 pub struct Map<K: PartialEq + Hash, V: PartialEq> {
   keys: Vec<K>,
   values: Vec<V>,
   hashes: Vec<u64>,
+  version: u64,
+}
+
+/// The single hash computation shared by every Map lookup path,
+/// so that keys and their borrowed forms always produce the same hash
+fn hash_of<Q: Hash + ?Sized> (key: &Q) -> u64 {
+  let mut hasher = DefaultHasher::new();
+
+  key.hash(&mut hasher);
+
+  hasher.finish()
 }
 
 impl<K: PartialEq + Hash, V: PartialEq> Map<K, V> {
   const DEFAULT_CAPACITY: usize = 256;
 
+  /// The number of pairs beyond which the linear scan lookup of a Map becomes inappropriate,
+  /// and a HashMap should be preferred
+  /// 
+  /// In debug builds, `insert` asserts that a Map does not grow past this
+  pub const LINEAR_SCAN_WARN_THRESHOLD: usize = 4096;
+
   /// Used by all Maps of a given type to generate hashes from keys
   #[inline]
   pub fn hash<EqK: Hash + ?Sized> (key: &EqK) -> u64
   where K: PartialEq<EqK>
   {
-    let mut hasher = DefaultHasher::new();
-
-    key.hash(&mut hasher);
-
-    hasher.finish()
+    hash_of(key)
   }
 
   /// Create a Map and pre-allocate its Vecs with a specified capacity
@@ -58,6 +75,7 @@ impl<K: PartialEq + Hash, V: PartialEq> Map<K, V> {
       keys: Vec::with_capacity(cap),
       values: Vec::with_capacity(cap),
       hashes: Vec::with_capacity(cap),
+      version: 0,
     }
   }
 
@@ -67,6 +85,26 @@ impl<K: PartialEq + Hash, V: PartialEq> Map<K, V> {
     Self::with_capacity(Self::DEFAULT_CAPACITY)
   }
 
+  /// Create a Map from parallel slices of keys and values,
+  /// cloning each (key, value) pair in with `insert`
+  /// 
+  /// # Panics
+  /// Panics if the slices are not the same length
+  pub fn from_slices (keys: &[K], values: &[V]) -> Self
+  where K: Clone,
+        V: Clone
+  {
+    assert_eq!(keys.len(), values.len(), "Map::from_slices given slices of different lengths");
+
+    let mut map = Self::with_capacity(keys.len());
+
+    for (key, value) in keys.iter().zip(values.iter()) {
+      map.insert(key.clone(), value.clone());
+    }
+
+    map
+  }
+
 
   #[inline]
   fn index_of_hashed_key<EqK: Hash + ?Sized> (&self, hash: u64, key: &EqK) -> Option<usize>
@@ -92,6 +130,26 @@ impl<K: PartialEq + Hash, V: PartialEq> Map<K, V> {
     self.index_of_hashed_key(Self::hash(key), key)
   }
 
+  /// Find the vec index of a key if it exists in a Map,
+  /// comparing against a borrowed form of the stored keys
+  pub fn index_of_borrowed_key<Q: Hash + Eq + ?Sized> (&self, key: &Q) -> Option<usize>
+  where K: Borrow<Q>
+  {
+    let hash = hash_of(key);
+
+    for (idx, own_hash) in self.hashes.iter().enumerate() {
+      if *own_hash == hash {
+        let own_key = unsafe { self.keys.get_unchecked(idx) };
+
+        if own_key.borrow() == key {
+          return Some(idx)
+        }
+      }
+    }
+
+    None
+  }
+
 
   /// Find the vec index of a value if it exists in a Map
   pub fn index_of_value (&self, value: &V) -> Option<usize> {
@@ -151,6 +209,21 @@ impl<K: PartialEq + Hash, V: PartialEq> Map<K, V> {
     self.values.is_empty()
   }
 
+  /// Determine if a Map has grown large enough that a HashMap would likely perform better,
+  /// based on `Map::LINEAR_SCAN_WARN_THRESHOLD`
+  #[inline]
+  pub fn would_benefit_from_hashmap (&self) -> bool {
+    self.len() > Self::LINEAR_SCAN_WARN_THRESHOLD
+  }
+
+  /// Get the number of changes made to a Map through its own methods,
+  /// which increases with every insertion, removal, or key replacement,
+  /// but not when values are mutated through references
+  #[inline]
+  pub fn version (&self) -> u64 {
+    self.version
+  }
+
 
   /// Get an immutable reference to a value associated with a given key in a Map,
   /// if it contains a pair with a matching key
@@ -178,6 +251,59 @@ impl<K: PartialEq + Hash, V: PartialEq> Map<K, V> {
     }
   }
 
+  /// Get an immutable reference to a value associated with a given key in a Map
+  /// 
+  /// # Panics
+  /// Panics with the given message if the Map does not contain a pair with a matching key
+  #[inline]
+  pub fn expect_value<EqK: Hash + ?Sized> (&self, key: &EqK, msg: &str) -> &V
+  where K: PartialEq<EqK>
+  {
+    self.find_value(key).expect(msg)
+  }
+
+  /// Get immutable references to the stored key and the value associated with a given key in a Map,
+  /// if it contains a pair with a matching key
+  #[inline]
+  pub fn find_pair<EqK: Hash + ?Sized> (&self, key: &EqK) -> Option<(&K, &V)>
+  where K: PartialEq<EqK>
+  {
+    self.index_of_key(key).map(|idx| unsafe { self.get_pair_unchecked(idx) })
+  }
+
+  /// Get an immutable reference to the stored key and a mutable reference to the value associated with a given key in a Map,
+  /// if it contains a pair with a matching key
+  /// 
+  /// This is the preferred way to access a pair for mutation, as the key cannot be changed
+  #[inline]
+  pub fn find_pair_mut<EqK: Hash + ?Sized> (&mut self, key: &EqK) -> Option<(&K, &mut V)>
+  where K: PartialEq<EqK>
+  {
+    let idx = self.index_of_key(key)?;
+
+    Some(unsafe { (self.keys.get_unchecked(idx), self.values.get_unchecked_mut(idx)) })
+  }
+
+  /// Get an immutable reference to a value associated with a given key in a Map,
+  /// looking up by a borrowed form of the key in the same manner as `std::collections::HashMap::get`
+  /// 
+  /// E.g. this allows a `Map<String, V>` to be searched with a `&str`, without allocating
+  #[inline]
+  pub fn get<Q: Hash + Eq + ?Sized> (&self, key: &Q) -> Option<&V>
+  where K: Borrow<Q>
+  {
+    self.index_of_borrowed_key(key).map(|idx| unsafe { self.values.get_unchecked(idx) })
+  }
+
+  /// Get a mutable reference to a value associated with a given key in a Map,
+  /// looking up by a borrowed form of the key in the same manner as `std::collections::HashMap::get_mut`
+  #[inline]
+  pub fn get_mut<Q: Hash + Eq + ?Sized> (&mut self, key: &Q) -> Option<&mut V>
+  where K: Borrow<Q>
+  {
+    self.index_of_borrowed_key(key).map(move |idx| unsafe { self.values.get_unchecked_mut(idx) })
+  }
+
 
   /// Get an immutable reference to a key associated with a given value in a Map,
   /// if it contains a pair with a matching value
@@ -221,6 +347,9 @@ impl<K: PartialEq + Hash, V: PartialEq> Map<K, V> {
   /// 
   /// Note that the Map type does not necessarily preserve its order,
   /// so index-based referencing is temporaly unstable
+  /// 
+  /// Mutating the key does not update its hash, and will break lookups;
+  /// use `set_key` to change a key
   #[inline]
   pub unsafe fn get_pair_unchecked_mut (&mut self, idx: usize) -> (&mut K, &mut V) {
     (self.keys.get_unchecked_mut(idx), self.values.get_unchecked_mut(idx))
@@ -248,6 +377,9 @@ impl<K: PartialEq + Hash, V: PartialEq> Map<K, V> {
   /// 
   /// Note that the Map type does not necessarily preserve its order,
   /// so index-based referencing is temporaly unstable
+  /// 
+  /// Mutating the key does not update its hash, and will break lookups;
+  /// use `set_key` to change a key, or `find_pair_mut` to only mutate the value
   #[inline]
   pub fn get_pair_mut (&mut self, idx: usize) -> Option<(&mut K, &mut V)> {
     if idx < self.len() {
@@ -271,18 +403,72 @@ impl<K: PartialEq + Hash, V: PartialEq> Map<K, V> {
         let own_key = unsafe { self.keys.get_unchecked(idx) };
 
         if own_key == &key {
+          self.version += 1;
+
           return Some(replace(unsafe { self.values.get_unchecked_mut(idx) }, value))
         }
       }
     }
 
+    debug_assert!(
+      self.len() < Self::LINEAR_SCAN_WARN_THRESHOLD,
+      "Map grew past LINEAR_SCAN_WARN_THRESHOLD ({}), consider using a HashMap instead",
+      Self::LINEAR_SCAN_WARN_THRESHOLD
+    );
+
     self.keys.push(key);
     self.values.push(value);
     self.hashes.push(hash);
+    self.version += 1;
 
     None
   }
 
+  /// Replace the key of a (key, value) pair in a Map by index, updating its hash
+  /// 
+  /// Returns the old key if the index is in range
+  /// 
+  /// If the index is out of range, or the new key is already bound to a different pair,
+  /// nothing is changed and the given key is returned in a Result::Err
+  /// 
+  /// Note that this is the only way to safely change a key in place,
+  /// as mutating a key without updating its hash will break lookups
+  pub fn set_key (&mut self, idx: usize, key: K) -> Result<K, K> {
+    if idx >= self.len() { return Err(key) }
+
+    let hash = Self::hash(&key);
+
+    if let Some(existing_idx) = self.index_of_hashed_key(hash, &key) {
+      if existing_idx != idx { return Err(key) }
+    }
+
+    unsafe { *self.hashes.get_unchecked_mut(idx) = hash };
+    self.version += 1;
+
+    Ok(replace(unsafe { self.keys.get_unchecked_mut(idx) }, key))
+  }
+
+  /// Get a mutable reference to the value associated with a given key in a Map,
+  /// inserting a default value first if the key is not yet bound
+  pub fn entry_or_default (&mut self, key: K) -> &mut V
+  where V: Default
+  {
+    let hash = Self::hash(&key);
+
+    let idx = if let Some(idx) = self.index_of_hashed_key(hash, &key) {
+      idx
+    } else {
+      self.hashes.push(hash);
+      self.keys.push(key);
+      self.values.push(V::default());
+      self.version += 1;
+
+      self.len() - 1
+    };
+
+    unsafe { self.values.get_unchecked_mut(idx) }
+  }
+
   /// Insert a value at the given key in a Map if they key does not already exist
   /// 
   /// Returns the (key, value) pair provided and does nothing if an existing key is found
@@ -296,6 +482,7 @@ impl<K: PartialEq + Hash, V: PartialEq> Map<K, V> {
     self.hashes.push(hash);
     self.keys.push(key);
     self.values.push(value);
+    self.version += 1;
 
     None
   }
@@ -311,6 +498,7 @@ impl<K: PartialEq + Hash, V: PartialEq> Map<K, V> {
     self.hashes.push(Self::hash(&key));
     self.keys.push(key);
     self.values.push(value);
+    self.version += 1;
 
     None
   }
@@ -325,7 +513,7 @@ impl<K: PartialEq + Hash, V: PartialEq> Map<K, V> {
   pub fn remove_by_index (&mut self, idx: usize) -> Option<(K, V)> {
     if idx < self.len() {
       self.hashes.swap_remove(idx);
-      self.keys.swap_remove(idx);
+      self.version += 1;
 
       Some((self.keys.swap_remove(idx), self.values.swap_remove(idx)))
     } else {
@@ -364,12 +552,72 @@ impl<K: PartialEq + Hash, V: PartialEq> Map<K, V> {
   pub fn pop (&mut self) -> Option<(K, V)> {
     if !self.is_empty() {
       self.hashes.pop();
+      self.version += 1;
+
       Some((self.keys.pop().unwrap(), self.values.pop().unwrap()))
     } else {
       None
     }
   }
 
+  /// Remove all (key, value) pairs from a Map for which a predicate returns false
+  /// 
+  /// The predicate receives each key and a mutable reference to its value, in pair order
+  /// 
+  /// Does not preserve order
+  pub fn retain<F: FnMut(&K, &mut V) -> bool> (&mut self, mut f: F) {
+    let mut idx = 0;
+
+    while idx < self.len() {
+      if f(unsafe { self.keys.get_unchecked(idx) }, unsafe { self.values.get_unchecked_mut(idx) }) {
+        idx += 1;
+      } else {
+        // remove_by_index swaps the last pair into idx, so it is visited next
+        self.remove_by_index(idx);
+      }
+    }
+  }
+
+  /// Remove all (key, value) pairs from a Map for which a predicate returns true,
+  /// and get the removed pairs
+  /// 
+  /// The predicate receives each key and a mutable reference to its value, in pair order
+  /// 
+  /// Note that this is the inverse of `retain`, which keeps the pairs for which its predicate returns true
+  /// 
+  /// Does not preserve order
+  pub fn drain_filter<F: FnMut(&K, &mut V) -> bool> (&mut self, mut f: F) -> Vec<(K, V)> {
+    let mut removed = Vec::new();
+    let mut idx = 0;
+
+    while idx < self.len() {
+      if f(unsafe { self.keys.get_unchecked(idx) }, unsafe { self.values.get_unchecked_mut(idx) }) {
+        // remove_by_index swaps the last pair into idx, so it is visited next
+        removed.push(self.remove_by_index(idx).unwrap());
+      } else {
+        idx += 1;
+      }
+    }
+
+    removed
+  }
+
+  /// Remove all (key, value) pairs from a Map whose key does not satisfy a predicate
+  /// 
+  /// Does not preserve order
+  #[inline]
+  pub fn retain_keys<F: FnMut(&K) -> bool> (&mut self, mut f: F) {
+    self.retain(|key, _| f(key))
+  }
+
+  /// Remove all (key, value) pairs from a Map whose value does not satisfy a predicate
+  /// 
+  /// Does not preserve order
+  #[inline]
+  pub fn retain_values<F: FnMut(&V) -> bool> (&mut self, mut f: F) {
+    self.retain(|_, value| f(value))
+  }
+
 
   /// Get an immutable slice of the keys of a Map
   #[inline]
@@ -421,19 +669,39 @@ impl<K: PartialEq + Hash, V: PartialEq> Map<K, V> {
     self.values.iter_mut()
   }
 
+  /// Call a closure with each key and a mutable reference to its associated value in a Map
+  #[inline]
+  pub fn update_values<F: FnMut(&K, &mut V)> (&mut self, mut f: F) {
+    for (key, value) in self.keys.iter().zip(self.values.iter_mut()) {
+      f(key, value);
+    }
+  }
+
 
   /// Get an immutable iterator over the (key, value) pairs of a Map
   #[inline]
-  pub fn iter (&self) -> PairIter<K, V> {
+  pub fn iter (&self) -> PairIter<'_, K, V> {
     PairIter::new(self)
   }
 
-  /// Get a mutable iterator over the (key, value) pairs of a Map
+  /// Get a (value) mutable iterator over the (key, value) pairs of a Map
   #[inline]
-  pub fn iter_mut (&mut self) -> PairIterMut<K, V> {
+  pub fn iter_mut (&mut self) -> PairIterMut<'_, K, V> {
     PairIterMut::new(self)
   }
 
+  /// Consume a Map and get an iterator over its keys
+  #[inline]
+  pub fn into_keys (self) -> VecIntoIter<K> {
+    self.keys.into_iter()
+  }
+
+  /// Consume a Map and get an iterator over its values
+  #[inline]
+  pub fn into_values (self) -> VecIntoIter<V> {
+    self.values.into_iter()
+  }
+
 
   /// Move the (key, value) pairs of another Map into a Map
   /// 
@@ -480,6 +748,26 @@ impl<K: PartialEq + Hash, V: PartialEq> Map<K, V> {
       self.insert(key, value);
     }
   }
+
+  /// Move the (key, value) pairs of another Map into a Map
+  /// 
+  /// Pairs whose key does not already exist in the Map are inserted,
+  /// otherwise `resolve` is called with the existing value and the incoming value to combine them
+  /// 
+  /// Consumes the other Map
+  pub fn merge_with<F: FnMut(&mut V, V)> (&mut self, other: Self, mut resolve: F) {
+    for ((key, value), hash) in other.keys.into_iter().zip(other.values).zip(other.hashes) {
+      if let Some(idx) = self.index_of_hashed_key(hash, &key) {
+        resolve(unsafe { self.values.get_unchecked_mut(idx) }, value);
+      } else {
+        self.keys.push(key);
+        self.values.push(value);
+        self.hashes.push(hash);
+      }
+
+      self.version += 1;
+    }
+  }
 }
 
 
@@ -502,6 +790,14 @@ where K: PartialEq<EqK>
   }
 }
 
+/// Maps are equal if they contain the same (key, value) pairs, regardless of order
+impl<K: PartialEq + Hash, V: PartialEq> PartialEq for Map<K, V> {
+  fn eq (&self, other: &Self) -> bool {
+    self.len() == other.len()
+    && self.iter().all(|(key, value)| other.find_value(key) == Some(value))
+  }
+}
+
 /// An iterator over (Key, Value) for a Map
 pub struct PairIter<'a, K: PartialEq + Hash + 'a, V: PartialEq + 'a> {
   keys: *const K,
@@ -544,17 +840,41 @@ impl<'a, K: PartialEq + Hash + 'a, V: PartialEq + 'a> Iterator for PairIter<'a,
       None
     }
   }
+
+  #[inline]
+  fn size_hint (&self) -> (usize, Option<usize>) {
+    let remaining = self.len - self.idx;
+    (remaining, Some(remaining))
+  }
 }
 
-/// An iterator over (mut Key, mut Value) for a Map
+impl<'a, K: PartialEq + Hash + 'a, V: PartialEq + 'a> DoubleEndedIterator for PairIter<'a, K, V> {
+  fn next_back (&mut self) -> Option<Self::Item> {
+    if self.idx < self.len {
+      self.len -= 1;
+      let pair_idx = self.len;
+
+      Some(unsafe { (&*self.keys.add(pair_idx), &*self.values.add(pair_idx)) })
+    } else {
+      None
+    }
+  }
+}
+
+impl<'a, K: PartialEq + Hash + 'a, V: PartialEq + 'a> ExactSizeIterator for PairIter<'a, K, V> { }
+
+/// An iterator over (Key, mut Value) for a Map
+/// 
+/// Keys are not yielded mutably, as changing a key in place would invalidate its hash,
+/// use `Map::set_key` to replace a key instead
 pub struct PairIterMut<'a, K: PartialEq + Hash + 'a, V: PartialEq + 'a> {
-  keys: *mut K,
+  keys: *const K,
   values: *mut V,
 
   idx: usize,
   len: usize,
 
-  k_phantom: PhantomData<&'a mut K>,
+  k_phantom: PhantomData<&'a K>,
   v_phantom: PhantomData<&'a mut V>,
 }
 
@@ -563,7 +883,7 @@ impl<'a, K: PartialEq + Hash + 'a, V: PartialEq + 'a> PairIterMut<'a, K, V> {
   #[inline]
   pub fn new (dict: &'a mut Map<K, V>) -> Self {
     Self {
-      keys: dict.keys.as_mut_ptr(),
+      keys: dict.keys.as_ptr(),
       values: dict.values.as_mut_ptr(),
 
       idx: 0,
@@ -576,20 +896,41 @@ impl<'a, K: PartialEq + Hash + 'a, V: PartialEq + 'a> PairIterMut<'a, K, V> {
 }
 
 impl<'a, K: PartialEq + Hash + 'a, V: PartialEq + 'a> Iterator for PairIterMut<'a, K, V> {
-  type Item = (&'a mut K, &'a mut V);
+  type Item = (&'a K, &'a mut V);
 
   fn next (&mut self) -> Option<Self::Item> {
     if self.idx < self.len {
       let pair_idx = self.idx;
       self.idx += 1;
 
-      Some(unsafe { (&mut *self.keys.add(pair_idx), &mut *self.values.add(pair_idx)) })
+      Some(unsafe { (&*self.keys.add(pair_idx), &mut *self.values.add(pair_idx)) })
     } else {
       None
     }
   }
+
+  #[inline]
+  fn size_hint (&self) -> (usize, Option<usize>) {
+    let remaining = self.len - self.idx;
+    (remaining, Some(remaining))
+  }
 }
 
+impl<'a, K: PartialEq + Hash + 'a, V: PartialEq + 'a> DoubleEndedIterator for PairIterMut<'a, K, V> {
+  fn next_back (&mut self) -> Option<Self::Item> {
+    if self.idx < self.len {
+      self.len -= 1;
+      let pair_idx = self.len;
+
+      Some(unsafe { (&*self.keys.add(pair_idx), &mut *self.values.add(pair_idx)) })
+    } else {
+      None
+    }
+  }
+}
+
+impl<'a, K: PartialEq + Hash + 'a, V: PartialEq + 'a> ExactSizeIterator for PairIterMut<'a, K, V> { }
+
 
 /// A by-value consuming iterator for a Map
 pub struct IntoIter<K: PartialEq + Hash, V: PartialEq> {
@@ -632,4 +973,305 @@ impl<K: PartialEq + Hash, V: PartialEq> FromIterator<(K, V)> for Map<K, V> {
 
     dict
   }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn iter_mut_preserves_lookup () {
+    let mut map: Map<String, i32> = vec![("a".to_owned(), 1), ("b".to_owned(), 2), ("c".to_owned(), 3)].into_iter().collect();
+
+    for (_, value) in map.iter_mut() {
+      *value *= 10;
+    }
+
+    assert_eq!(map.find_value("a"), Some(&10));
+    assert_eq!(map.find_value("b"), Some(&20));
+    assert_eq!(map.find_value("c"), Some(&30));
+
+    let idx = map.index_of_key("b").unwrap();
+    assert_eq!(map.set_key(idx, "c".to_owned()), Err("c".to_owned()));
+    assert_eq!(map.set_key(idx, "d".to_owned()), Ok("b".to_owned()));
+    assert!(!map.contains_key("b"));
+    assert_eq!(map.find_value("d"), Some(&20));
+  }
+
+  #[test]
+  fn iter_double_ended () {
+    let mut map: Map<&str, i32> = vec![("a", 1), ("b", 2), ("c", 3)].into_iter().collect();
+
+    let forward: Vec<(&&str, &i32)> = map.iter().collect();
+    let mut reversed: Vec<(&&str, &i32)> = map.iter().rev().collect();
+    reversed.reverse();
+
+    assert_eq!(forward, reversed);
+    assert_eq!(map.iter().len(), 3);
+
+    let mut iter = map.iter();
+    assert_eq!(iter.next(), Some((&"a", &1)));
+    assert_eq!(iter.next_back(), Some((&"c", &3)));
+    assert_eq!(iter.len(), 1);
+    assert_eq!(iter.next_back(), Some((&"b", &2)));
+    assert_eq!(iter.next(), None);
+
+    for (_, value) in map.iter_mut().rev().take(1) {
+      *value = 30;
+    }
+
+    assert_eq!(map.iter_mut().len(), 3);
+    assert_eq!(map.find_value(&"c"), Some(&30));
+  }
+
+  #[test]
+  fn remove_by_index () {
+    let mut map: Map<&str, i32> = vec![("a", 1), ("b", 2), ("c", 3), ("d", 4)].into_iter().collect();
+
+    assert_eq!(map.remove_by_index(0), Some(("a", 1)));
+    assert_eq!(map.len(), 3);
+    assert_eq!(map.keys(), &["d", "b", "c"]);
+    assert_eq!(map.values(), &[4, 2, 3]);
+
+    for (key, value) in [("b", 2), ("c", 3), ("d", 4)].iter() {
+      assert_eq!(map.find_value(key), Some(value));
+    }
+
+    assert_eq!(map.remove_by_index(3), None);
+    assert_eq!(map.remove_by_index(2), Some(("c", 3)));
+    assert_eq!(map.keys(), &["d", "b"]);
+  }
+
+  #[test]
+  #[should_panic(expected = "no entry for missing")]
+  fn expect_value () {
+    let map: Map<&str, i32> = vec![("a", 1)].into_iter().collect();
+
+    assert_eq!(*map.expect_value(&"a", "no entry for a"), 1);
+
+    map.expect_value(&"missing", "no entry for missing");
+  }
+
+  #[test]
+  fn version () {
+    let mut map: Map<&str, i32> = Map::new();
+    assert_eq!(map.version(), 0);
+
+    map.insert("a", 1);
+    map.insert("b", 2);
+    map.insert("c", 3);
+    let v1 = map.version();
+    assert!(v1 > 0);
+
+    assert_eq!(map.find_value(&"a"), Some(&1));
+    *map.find_value_mut(&"a").unwrap() = 10;
+    assert_eq!(map.version(), v1);
+
+    assert_eq!(map.remove_by_key(&"a"), Some(("a", 10)));
+    let v2 = map.version();
+    assert!(v2 > v1);
+
+    assert_eq!(map.remove_by_key(&"a"), None);
+    assert_eq!(map.version(), v2);
+
+    assert_eq!(map.keys(), &["c", "b"]);
+    assert_eq!(map.values(), &[3, 2]);
+
+    map.pop();
+    assert!(map.version() > v2);
+  }
+
+  #[test]
+  fn retain () {
+    let mut map: Map<i32, i32> = (0..10).map(|i| (i, i * 10)).collect();
+
+    map.retain(|key, value| {
+      *value += 1;
+      key % 2 == 0
+    });
+
+    assert_eq!(map.len(), 5);
+    assert_eq!(map.find_value(&4), Some(&41));
+    assert!(!map.contains_key(&3));
+
+    for (key, value) in map.iter() {
+      assert_eq!(map.find_value(key), Some(value));
+    }
+  }
+
+  #[test]
+  fn drain_filter () {
+    let mut map: Map<i32, i32> = (0..10).map(|i| (i, i * 10)).collect();
+
+    let mut removed = map.drain_filter(|key, _| key % 3 == 0);
+    removed.sort_unstable();
+
+    assert_eq!(removed, vec![(0, 0), (3, 30), (6, 60), (9, 90)]);
+    assert_eq!(map.len(), 6);
+
+    for (key, value) in map.iter() {
+      assert!(key % 3 != 0);
+      assert_eq!(*value, key * 10);
+      assert_eq!(map.find_value(key), Some(value));
+    }
+  }
+
+  #[test]
+  fn retain_keys () {
+    let mut map: Map<&str, i32> = vec![("keep", 1), ("drop", 2), ("keep_too", 3)].into_iter().collect();
+
+    map.retain_keys(|key| key.starts_with("keep"));
+
+    let mut keys = map.keys().to_vec();
+    keys.sort_unstable();
+    assert_eq!(keys, vec!["keep", "keep_too"]);
+  }
+
+  #[test]
+  fn retain_values () {
+    let mut map: Map<&str, i32> = vec![("a", 1), ("b", 20), ("c", 3), ("d", 40)].into_iter().collect();
+
+    map.retain_values(|value| *value < 10);
+
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.find_value(&"a"), Some(&1));
+    assert_eq!(map.find_value(&"c"), Some(&3));
+    assert!(!map.contains_value(&20) && !map.contains_value(&40));
+  }
+
+  #[test]
+  fn find_pair () {
+    let mut map: Map<String, i32> = Map::new();
+    map.insert("foo".to_owned(), 1);
+
+    let (key, value) = map.find_pair("foo").unwrap();
+    assert_eq!(key, &"foo".to_owned());
+    assert_eq!(value, &1);
+
+    assert!(map.find_pair("bar").is_none());
+  }
+
+  #[test]
+  fn entry_or_default () {
+    let mut counts: Map<&str, usize> = Map::new();
+
+    for word in "a b a c b a".split(' ') {
+      *counts.entry_or_default(word) += 1;
+    }
+
+    assert_eq!(counts.len(), 3);
+    assert_eq!(counts[&"a"], 3);
+    assert_eq!(counts[&"b"], 2);
+    assert_eq!(counts[&"c"], 1);
+  }
+
+  #[test]
+  fn eq_ignores_order () {
+    let a: Map<&str, i32> = vec![("a", 1), ("b", 2), ("c", 3)].into_iter().collect();
+    let mut b: Map<&str, i32> = vec![("c", 3), ("a", 1), ("b", 2)].into_iter().collect();
+
+    assert_eq!(a, b);
+
+    b[&"b"] = 4;
+
+    assert_ne!(a, b);
+
+    b.insert("d", 5);
+
+    assert_ne!(a, b);
+  }
+
+  #[test]
+  fn from_slices () {
+    let map = Map::from_slices(&["a", "b", "c"], &[1, 2, 3]);
+
+    assert_eq!(map.len(), 3);
+    assert_eq!(map[&"a"], 1);
+    assert_eq!(map[&"b"], 2);
+    assert_eq!(map[&"c"], 3);
+  }
+
+  #[test]
+  #[should_panic(expected = "different lengths")]
+  fn from_slices_mismatched () {
+    Map::from_slices(&["a", "b"], &[1]);
+  }
+
+  #[test]
+  fn borrowed_get () {
+    let mut map: Map<String, i32> = Map::new();
+
+    map.insert("one".to_owned(), 1);
+    map.insert("two".to_owned(), 2);
+
+    assert_eq!(map.get("one"), Some(&1));
+    assert_eq!(map.get(&"two".to_owned()), Some(&2));
+    assert_eq!(map.get("three"), None);
+
+    *map.get_mut("two").unwrap() += 10;
+
+    assert_eq!(map.get("two"), Some(&12));
+    assert_eq!(map.index_of_borrowed_key("two"), Some(1));
+  }
+
+  #[test]
+  fn merge_with () {
+    let mut a = Map::from_slices(&["x", "y"], &[1, 2]);
+    let b = Map::from_slices(&["y", "z"], &[10, 20]);
+
+    a.merge_with(b, |existing, incoming| *existing += incoming);
+
+    assert_eq!(a.len(), 3);
+    assert_eq!(a[&"x"], 1);
+    assert_eq!(a[&"y"], 12);
+    assert_eq!(a[&"z"], 20);
+  }
+
+  #[test]
+  fn find_pair_mut () {
+    let mut map = Map::from_slices(&["a", "b"], &[1, 2]);
+
+    let (key, value) = map.find_pair_mut(&"b").unwrap();
+    assert_eq!(*key, "b");
+    *value = 20;
+
+    assert_eq!(map.find_value(&"b"), Some(&20));
+    assert_eq!(map.find_value(&"a"), Some(&1));
+    assert!(map.find_pair_mut(&"c").is_none());
+  }
+
+  #[test]
+  fn into_keys_values () {
+    let map = Map::from_slices(&["a", "b", "c"], &[1, 2, 3]);
+
+    assert_eq!(map.clone().into_keys().collect::<Vec<_>>(), vec!["a", "b", "c"]);
+    assert_eq!(map.into_values().collect::<Vec<_>>(), vec![1, 2, 3]);
+  }
+
+  #[test]
+  #[cfg(debug_assertions)]
+  #[should_panic(expected = "LINEAR_SCAN_WARN_THRESHOLD")]
+  fn linear_scan_threshold () {
+    let mut map: Map<usize, usize> = Map::new();
+
+    for i in 0..Map::<usize, usize>::LINEAR_SCAN_WARN_THRESHOLD {
+      map.insert(i, i);
+    }
+
+    assert!(!map.would_benefit_from_hashmap());
+
+    map.insert(usize::MAX, 0);
+  }
+
+  #[test]
+  fn update_values () {
+    let mut map = Map::from_slices(&["a", "bb", "ccc"], &[1, 2, 3]);
+
+    map.update_values(|key, value| *value *= key.len());
+
+    assert_eq!(map[&"a"], 1);
+    assert_eq!(map[&"bb"], 4);
+    assert_eq!(map[&"ccc"], 9);
+  }
 }
\ No newline at end of file
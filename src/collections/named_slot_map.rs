@@ -81,9 +81,25 @@ impl<K: Key, V> NamedSlotMap<K, V> {
     self.slot_map.is_empty()
   }
 
+  /// Get the number of values a NamedSlotMap can hold without reallocating
+  /// (the lesser of the capacities of its SlotMap and BiMap)
+  #[inline]
+  pub fn capacity (&self) -> usize {
+    self.slot_map.capacity().min(self.id_bindings.capacity())
+  }
+
+  /// Reserve capacity for at least `additional` more values in a NamedSlotMap
+  #[inline]
+  pub fn reserve (&mut self, additional: usize) {
+    self.slot_map.reserve(additional);
+    self.id_bindings.reserve(additional);
+  }
+
 
 
   /// Determine if an ID is bound to any value in a NamedSlotMap
+  /// 
+  /// If the Key will be needed afterwards, use `key_for_id` instead to avoid a second lookup
   #[inline]
   pub fn contains_id (&self, id: &str) -> bool {
     self.id_bindings.contains_value(id)
@@ -114,6 +130,16 @@ impl<K: Key, V> NamedSlotMap<K, V> {
     self.id_bindings.find_key(id).unref()
   }
 
+  /// Get the Key for a value associated with a given ID in a NamedSlotMap,
+  /// if one exists
+  /// 
+  /// This is the preferred way to look up an ID,
+  /// as presence and the Key are determined by a single hash and scan
+  #[inline]
+  pub fn key_for_id (&self, id: &str) -> Option<K> {
+    self.find_key(id)
+  }
+
   /// Find the Key associated with a given value in a SlotMap,
   /// if it exists (and implements PartialEq)
   #[inline]
@@ -198,6 +224,28 @@ impl<K: Key, V> NamedSlotMap<K, V> {
     }
   }
 
+  /// Store a value in a NamedSlotMap with a given ID, and get a Key to retrieve it later
+  /// 
+  /// If a value is already registered with the given ID, it is replaced,
+  /// and the evicted ID and value are returned
+  pub fn insert_replace (&mut self, id: String, value: V) -> (K, Option<(String, V)>) {
+    if let Some(idx) = self.id_bindings.index_of_value(&id) {
+      let (existing_key, existing_id) = unsafe { self.id_bindings.get_pair_unchecked_mut(idx) };
+      let existing_key = *existing_key;
+      let old_id = replace(existing_id, id);
+
+      let existing_value = unsafe { self.slot_map.get_unchecked_mut(existing_key) };
+
+      (existing_key, Some((old_id, replace(existing_value, value))))
+    } else {
+      let new_key = self.slot_map.insert(value);
+
+      self.id_bindings.insert_at_value(id, new_key);
+
+      (new_key, None)
+    }
+  }
+
   /// Store a value in a NamedSlotMap with a given ID, and get a Key in a Result::Ok to retrieve it later
   /// 
   /// If a value is already registered with the given ID, does nothing and returns the value in a Result::Err
@@ -271,6 +319,14 @@ impl<K: Key, V> NamedSlotMap<K, V> {
     }
   }
 
+  /// Remove all values and IDs from a NamedSlotMap
+  /// 
+  /// All existing Keys are invalidated
+  pub fn clear (&mut self) {
+    self.slot_map.clear();
+    self.id_bindings.clear();
+  }
+
 
   /// Get an immutable slice of the IDs of a NamedSlotMap
   #[inline]
@@ -362,6 +418,24 @@ impl<K: Key, V> NamedSlotMap<K, V> {
   pub fn tri_iter_mut (&mut self) -> TriIterMut<K, V> {
     TriIterMut::new(self)
   }
+
+  /// Get an immutable iterator over the (ID, Key, value) tris of a NamedSlotMap, sorted by ID
+  /// 
+  /// This allocates and sorts a temporary index of the IDs, so it is O(n log n)
+  pub fn tri_iter_sorted (&self) -> impl Iterator<Item = (&String, K, &V)> + '_ {
+    let (keys, ids) = self.id_bindings.keys_values();
+
+    let mut order: Vec<usize> = (0..ids.len()).collect();
+
+    // IDs are unique, so an unstable sort is still deterministic
+    order.sort_unstable_by_key(|&idx| unsafe { ids.get_unchecked(idx) });
+
+    order.into_iter().map(move |idx| {
+      let key = unsafe { *keys.get_unchecked(idx) };
+
+      (unsafe { ids.get_unchecked(idx) }, key, unsafe { self.slot_map.get_unchecked(key) })
+    })
+  }
 }
 
 
@@ -526,4 +600,93 @@ impl<'a, K: Key, V> Iterator for TriIterMut<'a, K, V> {
       None
     }
   }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::collections::slot_map::DefaultKey;
+
+  #[test]
+  fn insert_replace () {
+    let mut nsm: NamedSlotMap<DefaultKey, usize> = NamedSlotMap::new();
+
+    let (k0, evicted) = nsm.insert_replace("foo".to_owned(), 1);
+    assert!(evicted.is_none());
+
+    let (k1, evicted) = nsm.insert_replace("foo".to_owned(), 2);
+    assert_eq!(k0, k1);
+    assert_eq!(evicted, Some(("foo".to_owned(), 1)));
+
+    assert_eq!(nsm.len(), 1);
+    assert_eq!(nsm["foo"], 2);
+    assert_eq!(nsm.find_id(k0).map(String::as_str), Some("foo"));
+  }
+
+  #[test]
+  fn key_for_id () {
+    let mut nsm: NamedSlotMap<DefaultKey, usize> = NamedSlotMap::new();
+
+    let k0 = nsm.insert_unique("foo".to_owned(), 1).unwrap();
+    let k1 = nsm.insert_unique("bar".to_owned(), 2).unwrap();
+
+    assert_eq!(nsm.key_for_id("foo"), Some(k0));
+    assert_eq!(nsm.key_for_id("bar"), Some(k1));
+    assert_eq!(nsm.key_for_id("baz"), None);
+  }
+
+  #[test]
+  fn tri_iter_sorted () {
+    let mut nsm: NamedSlotMap<DefaultKey, usize> = NamedSlotMap::new();
+
+    for (i, id) in ["delta", "alpha", "charlie", "bravo"].iter().enumerate() {
+      nsm.insert_unique((*id).to_owned(), i).unwrap();
+    }
+
+    nsm.remove(nsm.find_key("charlie").unwrap());
+
+    let sorted: Vec<(&str, DefaultKey, usize)> = nsm.tri_iter_sorted().map(|(id, key, &value)| (id.as_str(), key, value)).collect();
+
+    assert_eq!(sorted, vec![
+      ("alpha", nsm.find_key("alpha").unwrap(), 1),
+      ("bravo", nsm.find_key("bravo").unwrap(), 3),
+      ("delta", nsm.find_key("delta").unwrap(), 0),
+    ]);
+  }
+
+  #[test]
+  fn clear () {
+    let mut nsm: NamedSlotMap<DefaultKey, usize> = NamedSlotMap::new();
+
+    let k0 = nsm.insert_unique("foo".to_owned(), 1).unwrap();
+    let k1 = nsm.insert_unique("bar".to_owned(), 2).unwrap();
+
+    nsm.clear();
+
+    assert!(nsm.is_empty());
+    assert!(!nsm.contains_id("foo") && !nsm.contains_id("bar"));
+    assert!(!nsm.contains_key(k0) && !nsm.contains_key(k1));
+
+    let k2 = nsm.insert_unique("foo".to_owned(), 3).unwrap();
+    assert_ne!(k0, k2);
+    assert!(nsm.get(k0).is_none());
+    assert_eq!(nsm["foo"], 3);
+  }
+
+  #[test]
+  fn reserve () {
+    let mut nsm: NamedSlotMap<DefaultKey, usize> = NamedSlotMap::with_capacity(0);
+
+    nsm.reserve(500);
+
+    let cap = nsm.capacity();
+    assert!(cap >= 500);
+
+    for i in 0..500 {
+      nsm.insert_unique(format!("asset{}", i), i).unwrap();
+    }
+
+    assert_eq!(nsm.capacity(), cap);
+    assert_eq!(nsm["asset250"], 250);
+  }
 }
\ No newline at end of file
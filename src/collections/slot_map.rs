@@ -13,88 +13,149 @@ use std::{
   },
   vec::IntoIter as VecIntoIter,
   marker::PhantomData,
+  iter::{
+    FromIterator,
+    successors,
+  },
+  mem::{
+    size_of,
+    replace,
+  },
+  cmp::Reverse,
+  collections::HashMap,
+  convert::TryFrom,
 };
 
 use crate::POD;
 
 
+/// A classification trait for the unsigned integer types which can be used
+/// for the index and generation of SlotMap Keys
+pub trait KeyInt: POD + Eq + Ord + Hash {
+  /// The zero value of the integer type
+  const ZERO: Self;
+
+  /// The maximum value of the integer type
+  const MAX: Self;
+
+  /// Convert a usize to the integer type, if it is in range
+  fn try_from_usize (n: usize) -> Option<Self>;
+
+  /// Convert a usize to the integer type
+  /// 
+  /// Panics if the usize is out of range
+  #[inline]
+  fn from_usize (n: usize) -> Self {
+    Self::try_from_usize(n).expect("Attempted to convert an out of range usize to a KeyInt")
+  }
+
+  /// Convert the integer type to a usize
+  fn to_usize (self) -> usize;
+
+  /// Get the integer one greater than this one, if it is in range
+  fn checked_increment (self) -> Option<Self>;
+}
+
+macro_rules! impl_key_int {
+  ($($ty: ty),*) => { $(
+    impl KeyInt for $ty {
+      const ZERO: Self = 0;
+      const MAX: Self = <$ty>::MAX;
+
+      #[inline] fn try_from_usize (n: usize) -> Option<Self> { Self::try_from(n).ok() }
+      #[inline] fn to_usize (self) -> usize { self as usize }
+      #[inline] fn checked_increment (self) -> Option<Self> { self.checked_add(1) }
+    }
+  )* };
+}
+
+impl_key_int!(u8, u16, u32, u64, usize);
+
+
 /// The interior data type contained by SlotMap Keys
+/// 
+/// The index and generation are `u32` unless another KeyInt type is specified
+/// 
+/// A narrower KeyInt type limits both the number of slots a SlotMap may create,
+/// and the number of times each slot may be reused before it is retired
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
-pub struct KeyData {
-  idx: u32,
-  gen: u32,
+pub struct KeyData<I: KeyInt = u32> {
+  idx: I,
+  gen: I,
 }
 
-impl KeyData {
+impl<I: KeyInt> KeyData<I> {
   /// An uninitialized null value for a KeyData (The equivalent of KeyData::default() but const)
-  pub const NULL: Self = Self { idx: 0, gen: 0 };
+  pub const NULL: Self = Self { idx: I::ZERO, gen: I::ZERO };
 }
 
 /// The data type used by SlotMaps to map from Keys to values
 #[derive(Debug, Clone)]
-pub struct Slot {
-  idx: u32,
-  gen: u32,
+pub struct Slot<I: KeyInt = u32> {
+  idx: I,
+  gen: I,
 }
 
 /// A classification trait for types which can be used as a Key in a SlotMap
 /// 
 /// Use the `make_key_type!` macro to generate types which implements this trait
-pub trait Key: From<KeyData>
-             + Into<KeyData>
-             + Deref<Target = KeyData>
+pub trait Key: From<KeyData<<Self as Key>::Int>>
+             + Into<KeyData<<Self as Key>::Int>>
+             + Deref<Target = KeyData<<Self as Key>::Int>>
              + POD
              + Hash
-{ }
-
-impl<T> Key for T
-where T: From<KeyData>
-       + Into<KeyData> 
-       + Deref<Target = KeyData>
-       + POD
-       + Hash
-{ }
+{
+  /// The integer type used for the index and generation of the Key
+  type Int: KeyInt;
+}
 
 
 /// A wrapper macro to generate data and implementation for a unique Key type for use with SlotMaps
 /// 
 /// This allows type safety when using multiple SlotMaps,
 /// meaning a Key created for one SlotMap will not work for another, being rejected at compile time
+/// 
+/// The index and generation of the Key are `u32` by default,
+/// but another KeyInt type may be given after the name, e.g. `pub struct BigKey: u64;`
 #[macro_export]
 macro_rules! make_key_type {
-  ($(#[$meta:meta])* $vis: vis struct $name: ident ; $($rest: tt)*) => {
+  ($(#[$meta:meta])* $vis: vis struct $name: ident : $int: ty ; $($rest: tt)*) => {
     $(#[$meta])*
     #[repr(transparent)]
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
-    $vis struct $name($crate::collections::slot_map::KeyData);
+    $vis struct $name($crate::collections::slot_map::KeyData<$int>);
 
     impl $name {
       /// An uninitialized null value for a SlotMap key (the equivalent of Key::default(), but const)
       #[allow(dead_code)]
-      pub const NULL: Self = Self($crate::collections::slot_map::KeyData::NULL);
+      pub const NULL: Self = Self($crate::collections::slot_map::KeyData::<$int>::NULL);
     }
 
-    impl From<$crate::collections::slot_map::KeyData> for $name {
-      fn from (data: $crate::collections::slot_map::KeyData) -> Self {
+    impl $crate::collections::slot_map::Key for $name {
+      type Int = $int;
+    }
+
+    impl From<$crate::collections::slot_map::KeyData<$int>> for $name {
+      fn from (data: $crate::collections::slot_map::KeyData<$int>) -> Self {
         Self(data)
       }
     }
 
-    impl From<&$crate::collections::slot_map::KeyData> for $name {
-      fn from (data: &$crate::collections::slot_map::KeyData) -> Self {
+    impl From<&$crate::collections::slot_map::KeyData<$int>> for $name {
+      fn from (data: &$crate::collections::slot_map::KeyData<$int>) -> Self {
         Self(*data)
       }
     }
 
-    impl Into<$crate::collections::slot_map::KeyData> for $name {
-      fn into (self) -> $crate::collections::slot_map::KeyData {
-        self.0
+    impl From<$name> for $crate::collections::slot_map::KeyData<$int> {
+      fn from (key: $name) -> Self {
+        key.0
       }
     }
 
     impl std::ops::Deref for $name {
-      type Target = $crate::collections::slot_map::KeyData;
+      type Target = $crate::collections::slot_map::KeyData<$int>;
 
       fn deref (&self) -> &Self::Target {
         &self.0
@@ -104,6 +165,10 @@ macro_rules! make_key_type {
     $crate::make_key_type!($($rest)*);
   };
 
+  ($(#[$meta:meta])* $vis: vis struct $name: ident ; $($rest: tt)*) => {
+    $crate::make_key_type!($(#[$meta])* $vis struct $name: u32 ; $($rest)*);
+  };
+
   () => {};
 }
 
@@ -116,9 +181,44 @@ make_key_type! {
 
 
 #[derive(Debug, Clone)]
-struct FreeList {
-  head: u32,
-  tail: u32
+struct FreeList<I: KeyInt> {
+  head: I,
+  tail: I
+}
+
+
+/// Memory usage and fragmentation information for a SlotMap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotMapStats {
+  /// The number of values in the SlotMap
+  pub live: usize,
+  /// The total number of slots, live, free, or retired
+  pub slots: usize,
+  /// The number of slots in the freelist
+  pub free: usize,
+  /// The number of slots which have exhausted their generations and will not be reused
+  pub retired: usize,
+  /// The number of bytes allocated for the keys, values, and slots
+  pub bytes: usize,
+}
+
+
+/// A table mapping the Keys of a SlotMap from before a call to `reset_generations`
+/// to their equivalents afterwards
+#[derive(Debug, Clone)]
+pub struct GenerationRemap<K: Key> {
+  old_gens: Vec<Option<K::Int>>,
+}
+
+impl<K: Key> GenerationRemap<K> {
+  /// Get the new Key for a Key which was live before `reset_generations` was called,
+  /// or None if it was not live
+  pub fn get (&self, old_key: K) -> Option<K> {
+    match self.old_gens.get(old_key.idx.to_usize()) {
+      Some(&Some(old_gen)) if old_gen == old_key.gen => Some(KeyData { idx: old_key.idx, gen: K::Int::ZERO }.into()),
+      _ => None
+    }
+  }
 }
 
 
@@ -133,19 +233,60 @@ struct FreeList {
 /// This SlotMap type is implemented with a value-dense vec:
 /// Order is not preserved during value removal,
 /// but values are always tightly-packed for optimal data locality and iteration speed
-#[derive(Debug, Clone)]
+/// 
+/// When a SlotMap is dropped its values are dropped in value order,
+/// which after removals is not the same as insertion order;
+/// use `drop_in_reverse` where a deterministic order is required
+/// 
+/// A SlotMap may optionally be given a limit on its number of slots with `with_capacity_and_limit`,
+/// in which case `try_insert` can be used to insert values without exceeding it
+/// 
+/// Every insertion or removal increments the SlotMap's `version`,
+/// which can be compared against a previously observed version to cheaply detect changes
+#[derive(Debug)]
 pub struct SlotMap<K: Key, V> {
   keys: Vec<K>,
   values: Vec<V>,
-  slots: Vec<Slot>,
+  slots: Vec<Slot<K::Int>>,
 
-  freelist: Option<FreeList>,
+  freelist: Option<FreeList<K::Int>>,
+  max_slots: Option<usize>,
+  version: u64,
+  base_gen: K::Int,
 }
 
 impl<K: Key, V> Default for SlotMap<K, V> {
   #[inline] fn default () -> Self { Self::new() }
 }
 
+impl<K: Key, V: Clone> Clone for SlotMap<K, V> {
+  fn clone (&self) -> Self {
+    Self {
+      keys: self.keys.clone(),
+      values: self.values.clone(),
+      slots: self.slots.clone(),
+
+      freelist: self.freelist.clone(),
+      max_slots: self.max_slots,
+      version: self.version,
+      base_gen: self.base_gen,
+    }
+  }
+
+  /// Overwrite a SlotMap with a copy of another,
+  /// reusing the existing allocations where possible
+  fn clone_from (&mut self, source: &Self) {
+    self.keys.clone_from(&source.keys);
+    self.values.clone_from(&source.values);
+    self.slots.clone_from(&source.slots);
+
+    self.freelist = source.freelist.clone();
+    self.max_slots = source.max_slots;
+    self.version = source.version;
+    self.base_gen = source.base_gen;
+  }
+}
+
 impl<K: Key, V> SlotMap<K, V> {
   const DEFAULT_CAPACITY: usize = 256;
   
@@ -159,6 +300,22 @@ impl<K: Key, V> SlotMap<K, V> {
       slots: Vec::with_capacity(cap),
 
       freelist: None,
+      max_slots: None,
+      version: 0,
+      base_gen: K::Int::ZERO,
+    }
+  }
+
+  /// Create a new SlotMap, initialize its Vecs with a given capacity,
+  /// and limit the number of slots it may create
+  /// 
+  /// Once the limit is reached, `try_insert` fails unless a slot has been freed,
+  /// and other insertion methods panic
+  #[inline]
+  pub fn with_capacity_and_limit (cap: usize, max_slots: usize) -> Self {
+    Self {
+      max_slots: Some(max_slots),
+      .. Self::with_capacity(cap)
     }
   }
 
@@ -168,15 +325,47 @@ impl<K: Key, V> SlotMap<K, V> {
     Self::with_capacity(Self::DEFAULT_CAPACITY)
   }
 
+  /// Create a new SlotMap from the values of an iterator,
+  /// and get the Keys associated with each value, in iteration order
+  pub fn from_values<I: IntoIterator<Item = V>> (iter: I) -> (Self, Vec<K>) {
+    let mut map = Self::new();
+
+    let keys = iter.into_iter().map(|value| map.insert(value)).collect();
+
+    (map, keys)
+  }
+
+
+  /// Create a new SlotMap with the same Keys as a SlotMap,
+  /// and values produced by applying a function to each of its values
+  /// 
+  /// The slot layout is copied, so Keys from the original SlotMap remain valid for the new one
+  pub fn map_values<B, F: FnMut(&V) -> B> (&self, f: F) -> SlotMap<K, B> {
+    SlotMap {
+      keys: self.keys.clone(),
+      values: self.values.iter().map(f).collect(),
+      slots: self.slots.clone(),
+
+      freelist: self.freelist.clone(),
+      max_slots: self.max_slots,
+      version: self.version,
+      base_gen: self.base_gen,
+    }
+  }
+
 
   /// Determine if a SlotMap (still) has a value associated with a given Key
   #[inline]
   pub fn contains_key (&self, key: K) -> bool {
-    if let Some(slot) = self.slots.get(key.idx as usize) {
-      slot.gen == key.gen
-    } else {
-      false
-    }
+    self.value_idx_of(key).is_some()
+  }
+
+  /// Determine if a SlotMap (still) has a value associated with every Key in a slice
+  /// 
+  /// Stops at the first Key which is no longer valid
+  #[inline]
+  pub fn all_live (&self, keys: &[K]) -> bool {
+    keys.iter().all(|&key| self.contains_key(key))
   }
 
   
@@ -194,6 +383,16 @@ impl<K: Key, V> SlotMap<K, V> {
     None
   }
 
+  /// Get an iterator over every Key associated with a value equal to a given value in a SlotMap,
+  /// in value order
+  /// 
+  /// Unlike `find_key`, this does not stop at the first match
+  pub fn all_keys_for<'a> (&'a self, value: &'a V) -> impl Iterator<Item = K> + 'a
+  where V: PartialEq
+  {
+    self.key_value_refs().filter(move |(_, own_value)| *own_value == value).map(|(key, _)| key)
+  }
+
 
   /// Get an immutable slice of the keys of a SlotMap
   #[inline]
@@ -214,6 +413,32 @@ impl<K: Key, V> SlotMap<K, V> {
   }
 
 
+  /// Get an iterator over disjoint mutable chunks of the values in a SlotMap, in value order,
+  /// each `chunk_size` long except possibly the last
+  /// 
+  /// The chunks may be handed to separate (scoped) threads for parallel processing;
+  /// use `chunks_with_keys_mut` to also get the Keys for each chunk
+  /// 
+  /// # Panics
+  /// Panics if `chunk_size` is zero
+  #[inline]
+  pub fn par_chunks_mut (&mut self, chunk_size: usize) -> impl Iterator<Item = &mut [V]> {
+    self.values.chunks_mut(chunk_size)
+  }
+
+  /// Get an iterator over disjoint mutable chunks of the values in a SlotMap, in value order,
+  /// each paired with the matching chunk of Keys
+  /// 
+  /// See `par_chunks_mut` for more details
+  /// 
+  /// # Panics
+  /// Panics if `chunk_size` is zero
+  #[inline]
+  pub fn chunks_with_keys_mut (&mut self, chunk_size: usize) -> impl Iterator<Item = (&[K], &mut [V])> {
+    self.keys.chunks(chunk_size).zip(self.values.chunks_mut(chunk_size))
+  }
+
+
   /// Get an immutable iterator over the values in a SlotMap
   #[inline]
   pub fn iter (&self) -> SliceIter<V> {
@@ -233,6 +458,30 @@ impl<K: Key, V> SlotMap<K, V> {
     self.keys.iter()
   }
 
+  /// Get the Key of the first value in a SlotMap, in value order
+  #[inline]
+  pub fn first_key (&self) -> Option<K> {
+    self.keys.first().copied()
+  }
+
+  /// Get the Key of the last value in a SlotMap, in value order
+  /// 
+  /// This is the Key of the most recently inserted value only if nothing has been removed,
+  /// as removal moves the last value into the place of the removed one
+  #[inline]
+  pub fn last_key (&self) -> Option<K> {
+    self.keys.last().copied()
+  }
+
+  /// Get an owned snapshot of the Keys in a SlotMap, in value order
+  /// 
+  /// Unlike `key_iter`, this does not borrow the SlotMap,
+  /// so the result may be iterated while mutating it
+  #[inline]
+  pub fn collect_keys (&self) -> Vec<K> {
+    self.keys.clone()
+  }
+
   /// Get an immutable iterator over the (Key, value) pairs in a SlotMap, in value order
   #[inline]
   pub fn pair_iter (&self) -> PairIter<K, V> {
@@ -245,34 +494,135 @@ impl<K: Key, V> SlotMap<K, V> {
     PairIterMut::new(self)
   }
 
+  /// Get an immutable iterator over the (Key, value) pairs in a SlotMap, in value order,
+  /// with the Keys copied out rather than referenced
+  #[inline]
+  pub fn key_value_refs (&self) -> impl Iterator<Item = (K, &V)> + '_ {
+    self.keys.iter().copied().zip(self.values.iter())
+  }
+
+  /// Get a (value) mutable iterator over the (Key, value index, value) triples in a SlotMap, in value order
+  /// 
+  /// The value index is the position of the value in `values`,
+  /// suitable for indexing external buffers kept parallel to it
+  #[inline]
+  pub fn iter_mut_indexed (&mut self) -> impl Iterator<Item = (K, usize, &mut V)> + '_ {
+    self.keys.iter().copied().zip(self.values.iter_mut()).enumerate().map(|(idx, (key, value))| (key, idx, value))
+  }
+
+  /// Get an immutable iterator over the (Key, value) pairs in a SlotMap,
+  /// in ascending Key order (by slot index, then generation)
+  /// 
+  /// Unlike value order, this does not depend on the order of previous removals
+  /// 
+  /// This allocates and sorts a temporary index of the values, so it is O(n log n)
+  pub fn iter_sorted_by_key (&self) -> impl Iterator<Item = (K, &V)> + '_ {
+    let mut order: Vec<usize> = (0..self.len()).collect();
+
+    // live keys never share a slot index, so an unstable sort is still deterministic
+    order.sort_unstable_by_key(|&value_idx| **unsafe { self.keys.get_unchecked(value_idx) });
+
+    order.into_iter().map(move |value_idx| unsafe { (*self.keys.get_unchecked(value_idx), self.values.get_unchecked(value_idx)) })
+  }
+
+  /// Get a (value) mutable iterator over the (Key, value) pairs in a SlotMap,
+  /// in ascending Key order (by slot index, then generation)
+  /// 
+  /// Unlike value order, this does not depend on the order of previous removals
+  /// 
+  /// This allocates and sorts a temporary index of the values, so it is O(n log n)
+  pub fn pair_iter_mut_sorted (&mut self) -> impl Iterator<Item = (K, &mut V)> + '_ {
+    let keys = &self.keys;
+    let values = self.values.as_mut_ptr();
+
+    let mut order: Vec<usize> = (0..keys.len()).collect();
+
+    // live keys never share a slot index, so an unstable sort is still deterministic
+    order.sort_unstable_by_key(|&value_idx| **unsafe { keys.get_unchecked(value_idx) });
+
+    // each value index appears in the order exactly once, so the mutable references are disjoint
+    order.into_iter().map(move |value_idx| unsafe { (*keys.get_unchecked(value_idx), &mut *values.add(value_idx)) })
+  }
+
+  /// Consume a SlotMap and get an iterator over its Keys, in value order
+  #[inline]
+  pub fn into_keys (self) -> VecIntoIter<K> {
+    self.keys.into_iter()
+  }
+
+  /// Consume a SlotMap and get an iterator over its (Key, value) pairs, in value order
+  #[inline]
+  pub fn into_pairs (self) -> impl Iterator<Item = (K, V)> {
+    self.keys.into_iter().zip(self.values)
+  }
+
+  /// Get an iterator over every slot index in a SlotMap,
+  /// along with the Key currently associated with the slot, if it is live
+  /// 
+  /// This exposes the indirection layer of the SlotMap, and is primarily useful for diagnostics
+  pub fn slot_iter (&self) -> impl Iterator<Item = (K::Int, Option<K>)> + '_ {
+    self.slots.iter().enumerate().map(move |(slot_idx, slot)| {
+      let slot_idx = K::Int::from_usize(slot_idx);
+
+      if self.slot_is_live(slot_idx) {
+        (slot_idx, Some(KeyData { idx: slot_idx, gen: slot.gen }.into()))
+      } else {
+        (slot_idx, None)
+      }
+    })
+  }
+
 
   /// Get an immutable reference to a value associated with a given Key in a SlotMap,
   /// if it (still) exists
   /// 
   /// This bounds checks the slot index in the Key,
-  /// and then validates the generation count in the resulting slot
+  /// and then validates that the resulting slot is live and belongs to the Key
   #[inline]
   pub fn get (&self, key: K) -> Option<&V> {
-    let slot = self.slots.get(key.idx as usize)?;
+    let value_idx = self.value_idx_of(key)?;
 
-    if slot.gen == key.gen {
-      Some(unsafe { self.values.get_unchecked(slot.idx as usize) })
-    } else {
-      None
-    }
+    Some(unsafe { self.values.get_unchecked(value_idx) })
   }
 
   /// Get a mutable reference to a value associated with a given Key in a SlotMap,
   /// if it (still) exists
   /// 
   /// This bounds checks the slot index in the Key,
-  /// and then validates the generation count in the resulting slot
+  /// and then validates that the resulting slot is live and belongs to the Key
   #[inline]
   pub fn get_mut (&mut self, key: K) -> Option<&mut V> {
-    let slot = self.slots.get(key.idx as usize)?;
+    let value_idx = self.value_idx_of(key)?;
+
+    Some(unsafe { self.values.get_unchecked_mut(value_idx) })
+  }
+
+  /// Replace the value associated with a given Key in a SlotMap,
+  /// if it (still) exists
+  /// 
+  /// Returns the old value, or None without inserting if the Key is stale
+  #[inline]
+  pub fn set (&mut self, key: K, value: V) -> Option<V> {
+    self.get_mut(key).map(|own_value| replace(own_value, value))
+  }
+
+  /// Get the Key and an immutable reference to the value at a given value index in a SlotMap,
+  /// if the index is in range
+  #[inline]
+  pub fn get_pair (&self, value_idx: usize) -> Option<(K, &V)> {
+    if value_idx < self.len() {
+      Some(unsafe { (*self.keys.get_unchecked(value_idx), self.values.get_unchecked(value_idx)) })
+    } else {
+      None
+    }
+  }
 
-    if slot.gen == key.gen {
-      Some(unsafe { self.values.get_unchecked_mut(slot.idx as usize) })
+  /// Get the Key and a mutable reference to the value at a given value index in a SlotMap,
+  /// if the index is in range
+  #[inline]
+  pub fn get_pair_mut (&mut self, value_idx: usize) -> Option<(K, &mut V)> {
+    if value_idx < self.len() {
+      Some(unsafe { (*self.keys.get_unchecked(value_idx), self.values.get_unchecked_mut(value_idx)) })
     } else {
       None
     }
@@ -282,22 +632,58 @@ impl<K: Key, V> SlotMap<K, V> {
   /// by assuming it still exists
   /// 
   /// # Safety
-  /// This does **not** bounds check the slot index in the Key,
+  /// If debug asserts are not enabled,
+  /// this does **not** bounds check the slot index in the Key,
   /// and also does **not** validate the generation count in the resulting slot
+  /// 
+  /// If debug asserts are enabled, an invalid Key causes a panic
   #[inline]
   pub unsafe fn get_unchecked (&self, key: K) -> &V {
-    self.values.get_unchecked(self.slots.get_unchecked(key.idx as usize).idx as usize)
+    if cfg!(debug_assertions) {
+      self.get(key).unwrap_or_else(|| panic!("Attempted SlotMap::get_unchecked access to invalid key {:?}", key))
+    } else {
+      self.values.get_unchecked(self.slots.get_unchecked(key.idx.to_usize()).idx.to_usize())
+    }
   }
 
   /// Unsafely get a mutable reference to a value associated with a given Key in a SlotMap,
   /// by assuming it still exists
   /// 
   /// # Safety
-  /// This does **not** bounds check the slot index in the Key,
+  /// If debug asserts are not enabled,
+  /// this does **not** bounds check the slot index in the Key,
   /// and also does **not** validate the generation count in the resulting slot
+  /// 
+  /// If debug asserts are enabled, an invalid Key causes a panic
   #[inline]
   pub unsafe fn get_unchecked_mut (&mut self, key: K) -> &mut V {
-    self.values.get_unchecked_mut(self.slots.get_unchecked(key.idx as usize).idx as usize)
+    if cfg!(debug_assertions) {
+      self.get_mut(key).unwrap_or_else(|| panic!("Attempted SlotMap::get_unchecked_mut access to invalid key {:?}", key))
+    } else {
+      self.values.get_unchecked_mut(self.slots.get_unchecked(key.idx.to_usize()).idx.to_usize())
+    }
+  }
+
+  /// Unsafely get an immutable reference to a value associated with a given Key in a SlotMap,
+  /// by assuming its slot index is in range, but still validating its generation
+  /// 
+  /// Returns None if the Key is stale
+  /// 
+  /// # Safety
+  /// If debug asserts are not enabled,
+  /// this does **not** bounds check the slot index in the Key
+  /// 
+  /// If debug asserts are enabled, an out of range slot index causes a panic
+  #[inline]
+  pub unsafe fn get_validated_unchecked (&self, key: K) -> Option<&V> {
+    debug_assert!(key.idx.to_usize() < self.slots.len(), "Attempted SlotMap::get_validated_unchecked access to out of range key {:?}", key);
+
+    let value_idx = self.slots.get_unchecked(key.idx.to_usize()).idx.to_usize();
+
+    match self.keys.get(value_idx) {
+      Some(own_key) if **own_key == *key => Some(self.values.get_unchecked(value_idx)),
+      _ => None
+    }
   }
 
   
@@ -313,272 +699,1706 @@ impl<K: Key, V> SlotMap<K, V> {
     self.values.is_empty()
   }
 
+  /// Get the number of values a SlotMap can hold without reallocating its keys and values
+  #[inline]
+  pub fn capacity (&self) -> usize {
+    self.keys.capacity().min(self.values.capacity())
+  }
 
-  fn acquire_slot (&mut self, value_idx: u32) -> KeyData {
-    let slot_idx;
-    let slot;
-
-    if let Some(freelist) = self.freelist.as_mut() {
-      slot_idx = freelist.head;
-      slot = unsafe { self.slots.get_unchecked_mut(freelist.head as usize) };
-      
-      if freelist.tail != slot_idx {
-        freelist.head = slot.idx;
-      } else {
-        self.freelist = None;
-      }
-    } else {
-      slot_idx = self.slots.len() as u32;
-      
-      self.slots.push(Slot { idx: 0, gen: 0 });
+  /// Reserve capacity for at least `additional` more values in a SlotMap
+  #[inline]
+  pub fn reserve (&mut self, additional: usize) {
+    self.keys.reserve(additional);
+    self.values.reserve(additional);
+    self.slots.reserve(additional);
+  }
 
-      slot = unsafe { self.slots.get_unchecked_mut(slot_idx as usize) };
-    }
-    
-    slot.idx = value_idx;
-    
-    KeyData {
-      idx: slot_idx,
-      gen: slot.gen
+  /// Get memory usage and fragmentation information for a SlotMap
+  /// 
+  /// This walks the freelist, so it is linear in the number of free slots
+  pub fn stats (&self) -> SlotMapStats {
+    let free = self.free_slots().count();
+
+    SlotMapStats {
+      live: self.len(),
+      slots: self.slots.len(),
+      free,
+      retired: self.slots.len() - self.len() - free,
+      bytes: self.keys.capacity() * size_of::<K>()
+           + self.values.capacity() * size_of::<V>()
+           + self.slots.capacity() * size_of::<Slot<K::Int>>(),
     }
   }
 
 
-  fn free_slot (&mut self, free_idx: u32) {
-    let free_slot = unsafe { self.slots.get_unchecked_mut(free_idx as usize) };
-    free_slot.gen += 1;
+  /// Verify the internal invariants of a SlotMap, for use in tests
+  /// 
+  /// Checks that every value's Key refers to a distinct slot which refers back to that value,
+  /// that the freelist terminates and contains only slots which are not live and not retired,
+  /// and that the free, live, and retired slots together account for every slot
+  /// 
+  /// Returns a description of the first violation found, if any
+  pub fn check_consistency (&self) -> Result<(), String> {
+    if self.keys.len() != self.values.len() {
+      return Err(format!("{} keys but {} values", self.keys.len(), self.values.len()))
+    }
+
+    let mut referenced = vec![false; self.slots.len()];
 
-    if let Some(freelist) = self.freelist.as_mut() {
-      let old_tail = unsafe { self.slots.get_unchecked_mut(freelist.tail as usize) };
+    for (value_idx, key) in self.keys.iter().enumerate() {
+      let slot_idx = key.idx.to_usize();
 
-      old_tail.idx = free_idx;
-      
-      freelist.tail = free_idx;
-    } else {
-      self.freelist = Some(FreeList {
-        head: free_idx,
-        tail: free_idx,
-      });
-    }
-  }
+      let slot = self.slots.get(slot_idx).ok_or_else(|| format!("value {} has a key with out of range slot index {}", value_idx, slot_idx))?;
 
+      if referenced[slot_idx] {
+        return Err(format!("slot {} is referenced by more than one value", slot_idx))
+      }
 
-  /// Add a value to a SlotMap and get a Key to retrieve it later
-  #[inline]
-  pub fn insert (&mut self, value: V) -> K {
-    let key = self.acquire_slot(self.len() as u32).into();
-  
-    self.values.push(value);
-    self.keys.push(key);
+      referenced[slot_idx] = true;
 
-    key
-  }
+      if slot.idx.to_usize() != value_idx {
+        return Err(format!("value {} has a key for slot {}, which refers to value {}", value_idx, slot_idx, slot.idx.to_usize()))
+      }
 
-  /// Add a value to a SlotMap,
-  /// using a closure that receives the Key
-  /// that will be used to retrieve the value later
-  /// 
-  /// Also returns the Key associated with the value returned by the closure
-  #[inline]
-  pub fn insert_with_key<F: FnOnce(K) -> V> (&mut self, f: F) -> K {
-    let key = self.acquire_slot(self.len() as u32).into();
-    let value = f(key);
+      if slot.gen != key.gen {
+        return Err(format!("value {} has a key with generation {:?}, but slot {} has generation {:?}", value_idx, key.gen, slot_idx, slot.gen))
+      }
+    }
 
-    self.values.push(value);
-    self.keys.push(key);
+    let mut free = 0;
 
-    key
-  }
-  
+    if let Some(freelist) = self.freelist.as_ref() {
+      let mut slot_idx = freelist.head;
 
-  /// Remove the value associated with a given Key in a SlotMap,
-  /// if it (still) exists
-  /// 
-  /// Returns the value removed, if one was found
-  #[inline]
-  pub fn remove (&mut self, key: K) -> Option<V> {
-    let slot_idx = key.idx;
+      loop {
+        let slot = self.slots.get(slot_idx.to_usize()).ok_or_else(|| format!("freelist contains out of range slot index {:?}", slot_idx))?;
 
-    if let Some(slot) = self.slots.get(slot_idx as usize) {
-      if slot.gen == key.gen {
-        let value_idx = slot.idx as usize;
+        if referenced[slot_idx.to_usize()] {
+          return Err(format!("slot {:?} is in the freelist but is live or already visited", slot_idx))
+        }
 
-        self.keys.swap_remove(value_idx);
-        let value = self.values.swap_remove(value_idx);
-        
-        if let Some(key) = self.keys.get(value_idx) {
-          unsafe { self.slots.get_unchecked_mut(key.idx as usize) }.idx = value_idx as u32;
+        if slot.gen == K::Int::MAX {
+          return Err(format!("slot {:?} is in the freelist but is retired", slot_idx))
         }
 
-        self.free_slot(slot_idx);
+        referenced[slot_idx.to_usize()] = true;
+        free += 1;
 
-        return Some(value)
+        if slot_idx == freelist.tail { break }
+
+        slot_idx = slot.idx;
       }
     }
 
-    None
-  }
-}
+    let mut retired = 0;
 
-impl<K: Key, V> Index<K> for SlotMap<K, V> {
-  type Output = V;
+    for (slot_idx, slot) in self.slots.iter().enumerate() {
+      if !referenced[slot_idx] {
+        if slot.gen != K::Int::MAX {
+          return Err(format!("slot {} is neither live, free, nor retired", slot_idx))
+        }
 
-  fn index (&self, key: K) -> &Self::Output {
-    self.get(key).expect("Attempted SlotMap[] access to invalid key")
-  }
-}
+        retired += 1;
+      }
+    }
 
-impl<K: Key, V> IndexMut<K> for SlotMap<K, V> {
-  fn index_mut (&mut self, key: K) -> &mut Self::Output {
-    self.get_mut(key).expect("Attempted SlotMap[] access to invalid key")
+    if free + retired + self.len() != self.slots.len() {
+      return Err(format!("{} free, {} retired, and {} live slots, but {} total slots", free, retired, self.len(), self.slots.len()))
+    }
+
+    Ok(())
   }
-}
 
-impl<K: Key, V> IntoIterator for SlotMap<K, V> {
-  type Item = V;
-  type IntoIter = VecIntoIter<V>;
 
-  fn into_iter(self) -> Self::IntoIter {
-    self.values.into_iter()
+  /// Reset the generation of every live slot in a SlotMap to zero,
+  /// so that the Keys of its values are as small as possible, e.g. before serialization
+  /// 
+  /// Slot indices are unchanged, so values keep their positions,
+  /// but the Keys of all live values change; the returned GenerationRemap
+  /// gives the new Key for each previously live Key
+  /// 
+  /// Free slots keep their generations, so Keys later issued for them
+  /// remain distinct from any Key issued for them before the reset
+  /// 
+  /// Any Key held externally that is not rewritten via the remap must be discarded,
+  /// as once a slot that was live during the reset is reused,
+  /// an older Key for it may once again match its generation
+  pub fn reset_generations (&mut self) -> GenerationRemap<K> {
+    let mut old_gens = vec![None; self.slots.len()];
+
+    for key in self.keys.iter_mut() {
+      old_gens[key.idx.to_usize()] = Some(key.gen);
+
+      *key = KeyData { idx: key.idx, gen: K::Int::ZERO }.into();
+
+      unsafe { self.slots.get_unchecked_mut(key.idx.to_usize()) }.gen = K::Int::ZERO;
+    }
+
+    self.version += 1;
+
+    GenerationRemap { old_gens }
   }
-}
 
-/// An iterator over (Key, Value) for a SlotMap
-pub struct PairIter<'a, K: Key + 'a, V: 'a> {
-  len: usize,
-  idx: usize,
 
-  keys: *const K,
-  values: *const V,
+  /// Rebuild the slots of a SlotMap so that there are no free slots,
+  /// giving each value the next slot in value order, e.g. before serialization
+  /// 
+  /// Values keep their positions, but the Keys of all values change;
+  /// the returned HashMap gives the new Key for each previously live Key
+  /// 
+  /// Each rebuilt slot's generation exceeds both that of the Key of the value moving into it,
+  /// and that of any Key previously issued for the slot, so Keys which are not rewritten via the remap are invalidated
+  /// 
+  /// Slots whose generations are exhausted are skipped and retired rather than rebuilt
+  pub fn compact (&mut self) -> HashMap<K, K>
+  where K: Eq
+  {
+    let next_gen = |gen: K::Int| gen.checked_increment().filter(|&gen| gen != K::Int::MAX);
 
-  k_phantom: PhantomData<&'a K>,
-  v_phantom: PhantomData<&'a V>,
-}
+    let len = self.len();
+    let old_slots = replace(&mut self.slots, Vec::with_capacity(len));
+    let mut remap = HashMap::with_capacity(len);
 
-impl<'a, K: Key + 'a, V: 'a> PairIter<'a, K, V> {
-  /// Create a new PairIter for a SlotMap
-  #[inline]
-  pub fn new (map: &'a SlotMap<K, V>) -> PairIter<'a, K, V> {
-    Self {
-      len: map.len(),
-      idx: 0,
+    for (value_idx, key) in self.keys.iter_mut().enumerate() {
+      let gen = loop {
+        let old_gen = old_slots.get(self.slots.len()).map_or(self.base_gen, |slot| slot.gen);
 
-      keys: map.keys.as_ptr(),
-      values: map.values.as_ptr(),
+        if let Some(gen) = next_gen(old_gen.max(key.gen)) { break gen }
 
-      k_phantom: PhantomData,
-      v_phantom: PhantomData,
+        self.slots.push(Slot { idx: K::Int::ZERO, gen: K::Int::MAX });
+      };
+
+      let slot_idx = K::Int::try_from_usize(self.slots.len()).expect("Attempted to grow SlotMap beyond the index range of its Key type");
+      let new_key: K = KeyData { idx: slot_idx, gen }.into();
+
+      self.slots.push(Slot { idx: K::Int::from_usize(value_idx), gen });
+
+      remap.insert(*key, new_key);
+
+      *key = new_key;
+    }
+
+    // discarded slots may be created again, so new slots must start above any generation they issued;
+    // those which cannot be exceeded are kept, retired
+    let discarded = old_slots.get(self.slots.len() ..).unwrap_or(&[]);
+    let kept = discarded.iter().rposition(|slot| next_gen(slot.gen).is_none()).map_or(0, |idx| idx + 1);
+
+    self.slots.extend(discarded[.. kept].iter().map(|_| Slot { idx: K::Int::ZERO, gen: K::Int::MAX }));
+
+    if let Some(gen) = discarded[kept ..].iter().filter_map(|slot| next_gen(slot.gen)).max() {
+      self.base_gen = self.base_gen.max(gen);
     }
+
+    self.freelist = None;
+    self.version += 1;
+
+    remap
   }
-}
 
-/// An iterator over (Key, mut Value) for a SlotMap
-pub struct PairIterMut<'a, K: Key + 'a, V: 'a> {
-  len: usize,
-  idx: usize,
 
-  keys: *const K,
-  values: *mut V,
+  /// Get the number of structural changes made to a SlotMap,
+  /// which increases with every insertion or removal but not when values are mutated in place
+  #[inline]
+  pub fn version (&self) -> u64 {
+    self.version
+  }
 
-  k_phantom: PhantomData<&'a K>,
-  v_phantom: PhantomData<&'a mut V>,
-}
 
-impl<'a, K: Key, V: 'a> PairIterMut<'a, K, V> {
-  /// Create a new PairIterMut for a SlotMap
+  /// Get the maximum number of slots a SlotMap may create, if it was given a limit
   #[inline]
-  pub fn new (map: &'a mut SlotMap<K, V>) -> PairIterMut<'a, K, V> {
-    Self {
-      len: map.len(),
-      idx: 0,
+  pub fn slot_limit (&self) -> Option<usize> {
+    self.max_slots
+  }
 
-      keys: map.keys.as_ptr(),
-      values: map.values.as_mut_ptr(),
+  /// Determine if a SlotMap has no free slots and cannot create any more without exceeding its limit,
+  /// or the range of its Key type's index
+  #[inline]
+  pub fn is_full (&self) -> bool {
+    if self.freelist.is_some() { return false }
 
-      k_phantom: PhantomData,
-      v_phantom: PhantomData,
+    let at_limit = match self.max_slots {
+      Some(max_slots) => self.slots.len() >= max_slots,
+      None => false
+    };
+
+    at_limit || K::Int::try_from_usize(self.slots.len()).is_none()
+  }
+
+
+  /// Get an iterator over the indices of the free slots in a SlotMap,
+  /// in the order they will be reused by insertion
+  pub fn free_slots (&self) -> impl Iterator<Item = K::Int> + '_ {
+    let head = self.freelist.as_ref().map(|freelist| freelist.head);
+    let tail = self.freelist.as_ref().map(|freelist| freelist.tail);
+
+    // the tail's link is stale, so iteration must stop there rather than follow it
+    successors(head, move |&slot_idx| {
+      if Some(slot_idx) == tail {
+        None
+      } else {
+        Some(unsafe { self.slots.get_unchecked(slot_idx.to_usize()) }.idx)
+      }
+    })
+  }
+
+
+  fn acquire_slot (&mut self) -> KeyData<K::Int> {
+    let slot_idx;
+    let slot;
+
+    if let Some(freelist) = self.freelist.as_mut() {
+      slot_idx = freelist.head;
+      slot = unsafe { self.slots.get_unchecked_mut(freelist.head.to_usize()) };
+      
+      if freelist.tail != slot_idx {
+        freelist.head = slot.idx;
+      } else {
+        self.freelist = None;
+      }
+    } else {
+      if let Some(max_slots) = self.max_slots {
+        assert!(self.slots.len() < max_slots, "Attempted to grow SlotMap beyond its slot limit");
+      }
+
+      slot_idx = K::Int::try_from_usize(self.slots.len()).expect("Attempted to grow SlotMap beyond the index range of its Key type");
+      
+      self.slots.push(Slot { idx: K::Int::ZERO, gen: self.base_gen });
+
+      slot = unsafe { self.slots.get_unchecked_mut(slot_idx.to_usize()) };
+    }
+    
+    // there is always a slot per value, so if a slot index is in range, so is the value index
+    slot.idx = K::Int::from_usize(self.values.len());
+    
+    KeyData {
+      idx: slot_idx,
+      gen: slot.gen
     }
   }
-}
 
-impl<'a, K: Key, V: 'a> Iterator for PairIter<'a, K, V> {
-  type Item = (&'a K, &'a V);
 
-  fn next (&mut self) -> Option<Self::Item> {
-    if self.idx < self.len {
-      let value_idx = self.idx;
-      self.idx += 1;
+  fn free_slot (&mut self, free_idx: K::Int) {
+    let free_slot = unsafe { self.slots.get_unchecked_mut(free_idx.to_usize()) };
 
-      unsafe {
-        let key = &*self.keys.add(value_idx);
-        let value = &*self.values.add(value_idx);
+    // a slot whose generation cannot advance is retired rather than reused, so its Keys are never reissued;
+    // the maximum generation is reserved to mark retired slots
+    match free_slot.gen.checked_increment() {
+      Some(gen) if gen != K::Int::MAX => {
+        free_slot.gen = gen;
 
-        Some((key, value))
+        self.link_free_slot(free_idx);
       }
+
+      _ => free_slot.gen = K::Int::MAX
+    }
+  }
+
+
+  fn link_free_slot (&mut self, free_idx: K::Int) {
+    if let Some(freelist) = self.freelist.as_mut() {
+      let old_tail = unsafe { self.slots.get_unchecked_mut(freelist.tail.to_usize()) };
+
+      old_tail.idx = free_idx;
+      
+      freelist.tail = free_idx;
     } else {
-      None
+      self.freelist = Some(FreeList {
+        head: free_idx,
+        tail: free_idx,
+      });
     }
   }
-}
 
-impl<'a, K: Key + 'a, V: 'a> Iterator for PairIterMut<'a, K, V> {
-  type Item = (&'a K, &'a mut V);
 
-  fn next (&mut self) -> Option<Self::Item> {
-    if self.idx < self.len {
-      let value_idx = self.idx;
-      self.idx += 1;
+  fn unlink_free_slot (&mut self, free_idx: K::Int) {
+    let freelist = self.freelist.as_mut().expect("Attempted to unlink a slot from an empty freelist");
 
-      unsafe {
-        let key = &*self.keys.add(value_idx);
-        let value = &mut *self.values.add(value_idx);
+    if freelist.head == free_idx {
+      if freelist.tail == free_idx {
+        self.freelist = None;
+      } else {
+        freelist.head = unsafe { self.slots.get_unchecked(free_idx.to_usize()) }.idx;
+      }
 
-        Some((key, value))
+      return
+    }
+
+    let mut prev_idx = freelist.head;
+
+    loop {
+      assert!(prev_idx != freelist.tail, "Attempted to unlink a slot not in the freelist");
+
+      let next_idx = unsafe { self.slots.get_unchecked(prev_idx.to_usize()) }.idx;
+
+      if next_idx == free_idx {
+        if freelist.tail == free_idx {
+          freelist.tail = prev_idx;
+        } else {
+          let after_idx = unsafe { self.slots.get_unchecked(free_idx.to_usize()) }.idx;
+          unsafe { self.slots.get_unchecked_mut(prev_idx.to_usize()) }.idx = after_idx;
+        }
+
+        return
+      }
+
+      prev_idx = next_idx;
+    }
+  }
+
+
+  /// Get the value index associated with a Key, if its slot is live and belongs to it
+  /// 
+  /// Comparing the generation alone is not enough, as a stale Key may hold the generation of a free slot
+  /// (e.g. after `reset_generations`), and the index of a free slot is a freelist link rather than a value index
+  fn value_idx_of (&self, key: K) -> Option<usize> {
+    let value_idx = self.slots.get(key.idx.to_usize())?.idx.to_usize();
+
+    match self.keys.get(value_idx) {
+      Some(own_key) if **own_key == *key => Some(value_idx),
+      _ => None
+    }
+  }
+
+  fn slot_is_live (&self, slot_idx: K::Int) -> bool {
+    if let Some(slot) = self.slots.get(slot_idx.to_usize()) {
+      if let Some(key) = self.keys.get(slot.idx.to_usize()) {
+        return key.idx == slot_idx
       }
+    }
+
+    false
+  }
+
+
+  /// Add a value to a SlotMap and get a Key to retrieve it later
+  #[inline]
+  pub fn insert (&mut self, value: V) -> K {
+    let key = self.acquire_slot().into();
+  
+    self.values.push(value);
+    self.keys.push(key);
+    self.version += 1;
+
+    key
+  }
+
+  /// Add a value to a SlotMap and get a ScopedKey,
+  /// which removes the value again when it is dropped
+  /// 
+  /// The SlotMap remains borrowed for as long as the ScopedKey exists;
+  /// use `ScopedKey::leak` to keep the value and recover the plain Key
+  #[inline]
+  pub fn insert_scoped (&mut self, value: V) -> ScopedKey<'_, K, V> {
+    let key = self.insert(value);
+
+    ScopedKey { map: self, key }
+  }
+
+  /// Add a value to a SlotMap and get a Key to retrieve it later,
+  /// unless the SlotMap is full
+  /// 
+  /// If there are no free slots and the slot limit or the index range of the Key type has been reached,
+  /// does nothing and returns the value in a Result::Err
+  #[inline]
+  pub fn try_insert (&mut self, value: V) -> Result<K, V> {
+    if self.is_full() {
+      Err(value)
     } else {
-      None
+      Ok(self.insert(value))
     }
   }
-}
 
+  /// Add a value to a SlotMap,
+  /// using a closure that receives the Key
+  /// that will be used to retrieve the value later
+  /// 
+  /// Also returns the Key associated with the value returned by the closure
+  /// 
+  /// The slot is acquired before the closure is called, so the Key it receives is final,
+  /// but the value is not added until it returns; the Key is not valid for lookups until then
+  /// 
+  /// The closure cannot access the SlotMap, as it is mutably borrowed for the duration of the call,
+  /// so the slot can never be observed or reused in this intermediate state
+  #[inline]
+  pub fn insert_with_key<F: FnOnce(K) -> V> (&mut self, f: F) -> K {
+    let key = self.acquire_slot().into();
+    let value = f(key);
+
+    self.values.push(value);
+    self.keys.push(key);
+    self.version += 1;
 
-#[cfg(test)]
-mod tests {
-  #[test]
-  fn check_slot_map () {
-    let mut sm: super::SlotMap<super::DefaultKey, usize> = super::SlotMap::new();
+    key
+  }
   
-    let k0 = sm.insert(3200);
-    let k1 = sm.insert(6400);
 
-    assert_eq!(*sm.get(k0).expect("Failed to get k0"), 3200);
-    assert_eq!(*sm.get(k1).expect("Failed to get k1"), 6400);
-    assert_eq!(sm.get(super::KeyData { idx: 3, gen: 0 }.into()), None);
+  /// Get a mutable reference to the value associated with a given Key in a SlotMap,
+  /// or, if the Key is invalid, add a new value created by a closure
+  /// 
+  /// Returns the Key associated with the value,
+  /// which will differ from the given Key if a new value was added
+  pub fn get_mut_or_insert_with<F: FnOnce() -> V> (&mut self, key: K, f: F) -> (K, &mut V) {
+    let key = if self.contains_key(key) { key } else { self.insert(f()) };
 
-    assert_eq!(sm.remove(k0).expect("Failed to remove k0"), 3200);
+    (key, unsafe { self.get_unchecked_mut(key) })
+  }
 
-    let k2 = sm.insert(12800);
-    let k3 = sm.insert(25600);
+  /// Add a value to a SlotMap at a specific Key, such as one restored during deserialization
+  /// 
+  /// If the Key's slot index is beyond the current slots,
+  /// the slots in between are created and added to the freelist
+  /// 
+  /// The slot's generation is set to the Key's generation,
+  /// which may revalidate older Keys for the same slot holding that generation
+  /// 
+  /// If the slot is already occupied by a value or has been retired,
+  /// or the Key's slot index is beyond the slot limit,
+  /// does nothing and returns the value in a Result::Err
+  pub fn insert_at_key (&mut self, key: K, value: V) -> Result<(), V> {
+    let slot_idx = key.idx;
 
-    assert_eq!(sm.find_key(&12800), Some(k2));
+    if let Some(max_slots) = self.max_slots {
+      if slot_idx.to_usize() >= max_slots { return Err(value) }
+    }
 
-    let expect_keys = vec![k1, k2, k3];
+    if slot_idx.to_usize() < self.slots.len() {
+      if self.slot_is_live(slot_idx) { return Err(value) }
 
-    for (i, (k, v)) in sm.pair_iter_mut().enumerate() {
-      assert_eq!(&expect_keys[i], k);
+      if unsafe { self.slots.get_unchecked(slot_idx.to_usize()) }.gen == K::Int::MAX { return Err(value) }
 
-      let ov = *v;
-      *v += 1;
+      self.unlink_free_slot(slot_idx);
+    } else {
+      while self.slots.len() < slot_idx.to_usize() {
+        let free_idx = K::Int::from_usize(self.slots.len());
 
-      println!("{} | {:?} : {} + 1 = {}", i, k, ov, v);
+        self.slots.push(Slot { idx: K::Int::ZERO, gen: self.base_gen });
+        self.link_free_slot(free_idx);
+      }
+
+      self.slots.push(Slot { idx: K::Int::ZERO, gen: self.base_gen });
     }
 
-    let expect = vec![6401usize, 12801usize, 25601usize];
+    let value_idx = K::Int::from_usize(self.len());
 
-    for (i, (k, v)) in sm.pair_iter().enumerate() {
-      assert_eq!(&expect[i], v);
+    let slot = unsafe { self.slots.get_unchecked_mut(slot_idx.to_usize()) };
+    slot.idx = value_idx;
+    slot.gen = key.gen;
 
-      println!("{} | {:?} : {}", i, k, v);
+    self.values.push(value);
+    self.keys.push(key);
+    self.version += 1;
+
+    Ok(())
+  }
+  
+
+  /// Remove the value associated with a given Key in a SlotMap,
+  /// if it (still) exists
+  /// 
+  /// Returns the value removed, if one was found
+  #[inline]
+  pub fn remove (&mut self, key: K) -> Option<V> {
+    let value_idx = self.value_idx_of(key)?;
+
+    self.keys.swap_remove(value_idx);
+    let value = self.values.swap_remove(value_idx);
+    
+    if let Some(key) = self.keys.get(value_idx) {
+      unsafe { self.slots.get_unchecked_mut(key.idx.to_usize()) }.idx = K::Int::from_usize(value_idx);
+    }
+
+    self.free_slot(key.idx);
+    self.version += 1;
+
+    Some(value)
+  }
+
+  /// Remove the values associated with each of a sequence of Keys in a SlotMap,
+  /// if they (still) exist
+  /// 
+  /// Returns the value removed for each Key, in input order,
+  /// or None for Keys which were stale (including repeats of an earlier Key)
+  pub fn remove_many<I: IntoIterator<Item = K>> (&mut self, keys: I) -> Vec<Option<V>> {
+    keys.into_iter().map(|key| self.remove(key)).collect()
+  }
+
+  /// Remove all values from a SlotMap for which a predicate returns false,
+  /// and get the removed (Key, value) pairs
+  /// 
+  /// The predicate receives each Key and a mutable reference to its value, in value order
+  /// 
+  /// Does not preserve order
+  pub fn drain_filter<F: FnMut(K, &mut V) -> bool> (&mut self, mut f: F) -> Vec<(K, V)> {
+    let mut removed = Vec::new();
+    let mut value_idx = 0;
+
+    while value_idx < self.len() {
+      let key = unsafe { *self.keys.get_unchecked(value_idx) };
+
+      if f(key, unsafe { self.values.get_unchecked_mut(value_idx) }) {
+        value_idx += 1;
+      } else {
+        // remove swaps the last value into value_idx, so it is visited next
+        removed.push((key, self.remove(key).unwrap()));
+      }
+    }
+
+    removed
+  }
+
+  /// Remove all values from a SlotMap for which a predicate returns false
+  /// 
+  /// The predicate receives each Key and a mutable reference to its value, in value order
+  /// 
+  /// Does not preserve order
+  pub fn retain<F: FnMut(K, &mut V) -> bool> (&mut self, mut f: F) {
+    let mut value_idx = 0;
+
+    while value_idx < self.len() {
+      let key = unsafe { *self.keys.get_unchecked(value_idx) };
+
+      if f(key, unsafe { self.values.get_unchecked_mut(value_idx) }) {
+        value_idx += 1;
+      } else {
+        // remove swaps the last value into value_idx, so it is visited next
+        self.remove(key);
+      }
+    }
+  }
+
+  /// Remove all values from a SlotMap whose Key does not satisfy a predicate
+  /// 
+  /// Does not preserve order
+  #[inline]
+  pub fn retain_keys<F: FnMut(K) -> bool> (&mut self, mut f: F) {
+    self.retain(|key, _| f(key))
+  }
+
+  /// Remove all values from a SlotMap
+  /// 
+  /// The generation of every live slot is bumped,
+  /// so all existing Keys are invalidated
+  pub fn clear (&mut self) {
+    for value_idx in 0..self.keys.len() {
+      let slot_idx = unsafe { self.keys.get_unchecked(value_idx) }.idx;
+
+      self.free_slot(slot_idx);
+    }
+
+    self.keys.clear();
+    self.values.clear();
+    self.version += 1;
+  }
+
+  /// Consume a SlotMap, dropping its values in reverse Key order
+  /// 
+  /// Keys are ordered by slot index and then generation,
+  /// so when no slots have been reused this is the reverse of insertion order
+  pub fn drop_in_reverse (self) {
+    let mut pairs: Vec<(K, V)> = self.keys.into_iter().zip(self.values).collect();
+
+    pairs.sort_unstable_by_key(|(key, _)| Reverse(**key));
+
+    for pair in pairs {
+      drop(pair);
+    }
+  }
+}
+
+impl<K: Key, V> Index<K> for SlotMap<K, V> {
+  type Output = V;
+
+  fn index (&self, key: K) -> &Self::Output {
+    self.get(key).expect("Attempted SlotMap[] access to invalid key")
+  }
+}
+
+impl<K: Key, V> IndexMut<K> for SlotMap<K, V> {
+  fn index_mut (&mut self, key: K) -> &mut Self::Output {
+    self.get_mut(key).expect("Attempted SlotMap[] access to invalid key")
+  }
+}
+
+impl<K: Key, V> IntoIterator for SlotMap<K, V> {
+  type Item = V;
+  type IntoIter = VecIntoIter<V>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.values.into_iter()
+  }
+}
+
+impl<K: Key, V> Extend<V> for SlotMap<K, V> {
+  fn extend<I: IntoIterator<Item = V>> (&mut self, iter: I) {
+    for value in iter {
+      self.insert(value);
+    }
+  }
+}
+
+impl<K: Key, V> FromIterator<V> for SlotMap<K, V> {
+  fn from_iter<I: IntoIterator<Item = V>> (iter: I) -> Self {
+    let mut map = Self::new();
+
+    map.extend(iter);
+
+    map
+  }
+}
+
+/// A guard for a value inserted into a SlotMap with `insert_scoped`,
+/// which removes the value from the SlotMap when it is dropped
+pub struct ScopedKey<'a, K: Key, V> {
+  map: &'a mut SlotMap<K, V>,
+  key: K,
+}
+
+impl<'a, K: Key, V> ScopedKey<'a, K, V> {
+  /// Get the Key associated with the value of a ScopedKey
+  #[inline]
+  pub fn key (&self) -> K {
+    self.key
+  }
+
+  /// Consume a ScopedKey without removing its value from the SlotMap,
+  /// and get the Key associated with the value
+  #[inline]
+  pub fn leak (self) -> K {
+    let key = self.key;
+
+    std::mem::forget(self);
+
+    key
+  }
+}
+
+impl<'a, K: Key, V> Drop for ScopedKey<'a, K, V> {
+  fn drop (&mut self) {
+    self.map.remove(self.key);
+  }
+}
+
+/// An iterator over (Key, Value) for a SlotMap
+pub struct PairIter<'a, K: Key + 'a, V: 'a> {
+  len: usize,
+  idx: usize,
+
+  keys: *const K,
+  values: *const V,
+
+  k_phantom: PhantomData<&'a K>,
+  v_phantom: PhantomData<&'a V>,
+}
+
+impl<'a, K: Key + 'a, V: 'a> PairIter<'a, K, V> {
+  /// Create a new PairIter for a SlotMap
+  #[inline]
+  pub fn new (map: &'a SlotMap<K, V>) -> PairIter<'a, K, V> {
+    Self {
+      len: map.len(),
+      idx: 0,
+
+      keys: map.keys.as_ptr(),
+      values: map.values.as_ptr(),
+
+      k_phantom: PhantomData,
+      v_phantom: PhantomData,
+    }
+  }
+}
+
+/// An iterator over (Key, mut Value) for a SlotMap
+pub struct PairIterMut<'a, K: Key + 'a, V: 'a> {
+  len: usize,
+  idx: usize,
+
+  keys: *const K,
+  values: *mut V,
+
+  k_phantom: PhantomData<&'a K>,
+  v_phantom: PhantomData<&'a mut V>,
+}
+
+impl<'a, K: Key, V: 'a> PairIterMut<'a, K, V> {
+  /// Create a new PairIterMut for a SlotMap
+  #[inline]
+  pub fn new (map: &'a mut SlotMap<K, V>) -> PairIterMut<'a, K, V> {
+    Self {
+      len: map.len(),
+      idx: 0,
+
+      keys: map.keys.as_ptr(),
+      values: map.values.as_mut_ptr(),
+
+      k_phantom: PhantomData,
+      v_phantom: PhantomData,
+    }
+  }
+}
+
+impl<'a, K: Key, V: 'a> Iterator for PairIter<'a, K, V> {
+  type Item = (&'a K, &'a V);
+
+  fn next (&mut self) -> Option<Self::Item> {
+    if self.idx < self.len {
+      let value_idx = self.idx;
+      self.idx += 1;
+
+      unsafe {
+        let key = &*self.keys.add(value_idx);
+        let value = &*self.values.add(value_idx);
+
+        Some((key, value))
+      }
+    } else {
+      None
+    }
+  }
+}
+
+impl<'a, K: Key + 'a, V: 'a> Iterator for PairIterMut<'a, K, V> {
+  type Item = (&'a K, &'a mut V);
+
+  fn next (&mut self) -> Option<Self::Item> {
+    if self.idx < self.len {
+      let value_idx = self.idx;
+      self.idx += 1;
+
+      unsafe {
+        let key = &*self.keys.add(value_idx);
+        let value = &mut *self.values.add(value_idx);
+
+        Some((key, value))
+      }
+    } else {
+      None
+    }
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  fn check_slot_map () {
+    let mut sm: super::SlotMap<super::DefaultKey, usize> = super::SlotMap::new();
+  
+    let k0 = sm.insert(3200);
+    let k1 = sm.insert(6400);
+
+    assert_eq!(*sm.get(k0).expect("Failed to get k0"), 3200);
+    assert_eq!(*sm.get(k1).expect("Failed to get k1"), 6400);
+    assert_eq!(sm.get(super::KeyData { idx: 3, gen: 0 }.into()), None);
+
+    assert_eq!(sm.remove(k0).expect("Failed to remove k0"), 3200);
+
+    let k2 = sm.insert(12800);
+    let k3 = sm.insert(25600);
+
+    assert_eq!(sm.find_key(&12800), Some(k2));
+
+    let expect_keys = vec![k1, k2, k3];
+
+    for (i, (k, v)) in sm.pair_iter_mut().enumerate() {
+      assert_eq!(&expect_keys[i], k);
+
+      let ov = *v;
+      *v += 1;
+
+      println!("{} | {:?} : {} + 1 = {}", i, k, ov, v);
+    }
+
+    let expect = vec![6401usize, 12801usize, 25601usize];
+
+    for (i, (k, v)) in sm.pair_iter().enumerate() {
+      assert_eq!(&expect[i], v);
+
+      println!("{} | {:?} : {}", i, k, v);
+    }
+  }
+
+  #[test]
+  fn collect () {
+    let sm: super::SlotMap<super::DefaultKey, usize> = (0..10).collect();
+
+    assert_eq!(sm.len(), 10);
+
+    let (sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..10);
+
+    assert_eq!(keys.len(), 10);
+
+    for (i, key) in keys.into_iter().enumerate() {
+      assert_eq!(sm[key], i);
+    }
+  }
+
+  #[test]
+  fn drain_filter () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..10);
+
+    let mut removed = sm.drain_filter(|_, v| *v % 3 == 0);
+    removed.sort_by_key(|&(_, v)| v);
+
+    assert_eq!(removed.iter().map(|&(_, v)| v).collect::<Vec<_>>(), vec![1, 2, 4, 5, 7, 8]);
+    assert_eq!(sm.len(), 4);
+
+    for (k, v) in removed {
+      assert_eq!(keys[v], k);
+      assert!(!sm.contains_key(k));
+    }
+
+    for &i in [0, 3, 6, 9].iter() {
+      assert_eq!(sm[keys[i]], i);
+    }
+  }
+
+  #[test]
+  #[cfg(debug_assertions)]
+  #[should_panic(expected = "invalid key")]
+  fn get_unchecked_stale_key () {
+    let mut sm: super::SlotMap<super::DefaultKey, usize> = super::SlotMap::new();
+
+    let k0 = sm.insert(1);
+    sm.remove(k0);
+    sm.insert(2);
+
+    unsafe { sm.get_unchecked(k0) };
+  }
+
+  #[test]
+  fn stats () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, u64>::from_values(0..8);
+
+    sm.remove(keys[1]);
+    sm.remove(keys[4]);
+    sm.remove(keys[6]);
+
+    let stats = sm.stats();
+
+    assert_eq!(stats.live, 5);
+    assert_eq!(stats.slots, 8);
+    assert_eq!(stats.free, 3);
+    assert!(stats.bytes >= 8 * (std::mem::size_of::<super::DefaultKey>() + 8 + std::mem::size_of::<super::Slot>()));
+
+    sm.insert(8);
+
+    assert_eq!(sm.stats().free, 2);
+  }
+
+  #[test]
+  fn insert_at_key () {
+    let mut sm: super::SlotMap<super::DefaultKey, usize> = super::SlotMap::new();
+
+    let k0: super::DefaultKey = super::KeyData { idx: 3, gen: 5 }.into();
+    assert_eq!(sm.insert_at_key(k0, 10), Ok(()));
+    assert_eq!(sm.insert_at_key(k0, 11), Err(11));
+    assert_eq!(sm.get(k0), Some(&10));
+    assert_eq!(sm.stats().free, 3);
+
+    let k1: super::DefaultKey = super::KeyData { idx: 1, gen: 2 }.into();
+    assert_eq!(sm.insert_at_key(k1, 20), Ok(()));
+    assert_eq!(sm.get(k1), Some(&20));
+    assert_eq!(sm.stats().free, 2);
+
+    let k2 = sm.insert(30);
+    let k3 = sm.insert(40);
+    assert_eq!(sm.stats().free, 0);
+    assert!(k2.idx != 1 && k2.idx != 3 && k3.idx != 1 && k3.idx != 3);
+
+    for (k, v) in [(k0, 10), (k1, 20), (k2, 30), (k3, 40)].iter() {
+      assert_eq!(sm[*k], *v);
+    }
+  }
+
+  #[test]
+  fn insert_at_key_free_slots () {
+    let mut sm: super::SlotMap<super::DefaultKey, usize> = super::SlotMap::new();
+
+    let k0: super::DefaultKey = super::KeyData { idx: 3, gen: 5 }.into();
+    assert_eq!(sm.insert_at_key(k0, 10), Ok(()));
+
+    // the padding slots are free with generation 0
+    assert!(!sm.contains_key(super::DefaultKey::NULL));
+    assert!(sm.get(super::DefaultKey::NULL).is_none());
+    assert!(sm.get_mut(super::DefaultKey::NULL).is_none());
+    assert!(sm.remove(super::DefaultKey::NULL).is_none());
+
+    let k1: super::DefaultKey = super::KeyData { idx: 1, gen: 2 }.into();
+    assert_eq!(sm.insert_at_key(k1, 20), Ok(()));
+    assert_eq!(sm.remove(k1), Some(20));
+
+    // the freed slot now holds the next generation
+    let k1_next: super::DefaultKey = super::KeyData { idx: 1, gen: 3 }.into();
+    assert!(!sm.contains_key(k1_next));
+    assert!(sm.get(k1_next).is_none());
+    assert!(sm.remove(k1_next).is_none());
+
+    assert_eq!(sm.get(k0), Some(&10));
+    assert!(sm.check_consistency().is_ok());
+  }
+
+  #[test]
+  fn key_value_refs () {
+    let (sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..4);
+
+    let pairs: Vec<(super::DefaultKey, &usize)> = sm.key_value_refs().collect();
+
+    assert_eq!(pairs.len(), 4);
+
+    for (k, v) in pairs {
+      assert_eq!(keys[*v], k);
+    }
+  }
+
+  #[test]
+  fn clone_from () {
+    let (mut source, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..8);
+    source.remove(keys[2]);
+
+    let mut dest: super::SlotMap<super::DefaultKey, usize> = super::SlotMap::with_capacity(1024);
+    dest.insert(100);
+
+    let before = dest.stats().bytes;
+
+    dest.clone_from(&source);
+
+    assert_eq!(dest.stats().bytes, before);
+    assert_eq!(dest.len(), 7);
+    assert!(!dest.contains_key(keys[2]));
+    assert_eq!(dest[keys[5]], 5);
+
+    let k = dest.insert(200);
+    assert_eq!(k, source.insert(200));
+  }
+
+  #[test]
+  fn get_mut_or_insert_with () {
+    let mut sm: super::SlotMap<super::DefaultKey, usize> = super::SlotMap::new();
+
+    let k0 = sm.insert(1);
+
+    let (k, v) = sm.get_mut_or_insert_with(k0, || 2);
+    assert_eq!(k, k0);
+    *v += 10;
+    assert_eq!(sm[k0], 11);
+
+    sm.remove(k0);
+
+    let (k1, v) = sm.get_mut_or_insert_with(k0, || 2);
+    assert_ne!(k1, k0);
+    assert_eq!(*v, 2);
+    assert_eq!(sm.len(), 1);
+    assert!(sm.get(k0).is_none());
+  }
+
+  #[test]
+  fn retain_keys () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..8);
+
+    let blacklist = [keys[0], keys[3], keys[7]];
+
+    sm.retain_keys(|key| !blacklist.contains(&key));
+
+    assert_eq!(sm.len(), 5);
+
+    for (i, key) in keys.iter().enumerate() {
+      assert_eq!(sm.contains_key(*key), !blacklist.contains(key));
+
+      if let Some(value) = sm.get(*key) {
+        assert_eq!(*value, i);
+      }
+    }
+  }
+
+  #[test]
+  fn drop_in_reverse () {
+    use std::{ rc::Rc, cell::RefCell };
+
+    struct Recorder(usize, Rc<RefCell<Vec<usize>>>);
+
+    impl Drop for Recorder {
+      fn drop (&mut self) { self.1.borrow_mut().push(self.0) }
+    }
+
+    let record = Rc::new(RefCell::new(Vec::new()));
+    let mut sm: super::SlotMap<super::DefaultKey, Recorder> = super::SlotMap::new();
+
+    let keys: Vec<super::DefaultKey> = (0..5).map(|i| sm.insert(Recorder(i, record.clone()))).collect();
+
+    sm.remove(keys[1]);
+    assert_eq!(*record.borrow(), vec![1]);
+
+    sm.drop_in_reverse();
+    assert_eq!(*record.borrow(), vec![1, 4, 3, 2, 0]);
+  }
+
+  #[test]
+  fn try_insert () {
+    let mut sm: super::SlotMap<super::DefaultKey, usize> = super::SlotMap::with_capacity_and_limit(0, 4);
+
+    let keys: Vec<super::DefaultKey> = (0..4).map(|i| sm.try_insert(i).unwrap()).collect();
+
+    assert!(sm.is_full());
+    assert_eq!(sm.try_insert(4), Err(4));
+
+    sm.remove(keys[2]);
+
+    let k = sm.try_insert(5).unwrap();
+    assert_eq!(k.idx, 2);
+    assert_eq!(sm.try_insert(6), Err(6));
+    assert_eq!(sm.stats().slots, 4);
+
+    let beyond: super::DefaultKey = super::KeyData { idx: 4, gen: 0 }.into();
+    assert_eq!(sm.insert_at_key(beyond, 7), Err(7));
+  }
+
+  #[test]
+  fn iter_mut_indexed () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..6);
+
+    sm.remove(keys[1]);
+
+    let scratch: Vec<usize> = sm.values().iter().map(|v| v * 10).collect();
+
+    for (key, idx, value) in sm.iter_mut_indexed() {
+      assert_eq!(scratch[idx], *value * 10);
+      assert_eq!(keys[*value], key);
+
+      *value += 100;
+    }
+
+    for (idx, value) in sm.values().iter().enumerate() {
+      assert_eq!(scratch[idx] / 10 + 100, *value);
+    }
+  }
+
+  #[test]
+  fn free_slots () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..8);
+
+    assert_eq!(sm.free_slots().count(), 0);
+
+    sm.remove(keys[5]);
+    sm.remove(keys[2]);
+    sm.remove(keys[6]);
+
+    assert_eq!(sm.free_slots().collect::<Vec<u32>>(), vec![5, 2, 6]);
+
+    sm.insert(8);
+
+    assert_eq!(sm.free_slots().collect::<Vec<u32>>(), vec![2, 6]);
+  }
+
+  #[test]
+  fn wide_keys () {
+    crate::make_key_type! {
+      struct BigKey: u64;
+    }
+
+    assert_eq!(std::mem::size_of::<BigKey>(), 16);
+
+    let mut sm: super::SlotMap<BigKey, usize> = super::SlotMap::new();
+
+    // mock a slot whose generation has already churned through the range of u32
+    let stale: BigKey = super::KeyData { idx: 0, gen: u32::MAX as u64 }.into();
+    assert_eq!(sm.insert_at_key(stale, 10), Ok(()));
+    assert_eq!(sm.remove(stale), Some(10));
+
+    let k = sm.insert(20);
+
+    assert_eq!(k.idx, 0);
+    assert_eq!(k.gen, u32::MAX as u64 + 1);
+    assert!(sm.get(stale).is_none());
+    assert_eq!(sm[k], 20);
+  }
+
+  crate::make_key_type! {
+    struct TinyKey: u8;
+  }
+
+  #[test]
+  fn narrow_keys_index_range () {
+    let (mut sm, keys) = super::SlotMap::<TinyKey, usize>::from_values(0..256);
+
+    assert!(sm.is_full());
+    assert_eq!(sm.try_insert(256), Err(256));
+    assert!(sm.check_consistency().is_ok());
+
+    for (i, &key) in keys.iter().enumerate() {
+      assert_eq!(sm[key], i);
+    }
+
+    sm.remove(keys[7]);
+    assert!(!sm.is_full());
+
+    let k = sm.try_insert(256).unwrap();
+    assert_eq!(k.idx, 7);
+    assert!(sm.check_consistency().is_ok());
+  }
+
+  #[test]
+  #[should_panic(expected = "index range of its Key type")]
+  fn narrow_keys_index_range_insert () {
+    let (mut sm, _) = super::SlotMap::<TinyKey, usize>::from_values(0..256);
+
+    sm.insert(256);
+  }
+
+  #[test]
+  fn narrow_keys_generation_range () {
+    let mut sm: super::SlotMap<TinyKey, usize> = super::SlotMap::new();
+    let mut issued = std::collections::HashSet::new();
+
+    for i in 0..300 {
+      let key = sm.insert(i);
+
+      assert!(issued.insert(key), "key {:?} was reissued", key);
+      assert_eq!(sm.remove(key), Some(i));
+      assert!(sm.check_consistency().is_ok());
+    }
+
+    // slot 0 issues generations 0 through 254, then is retired in favour of slot 1
+    assert_eq!(issued.iter().filter(|key| key.idx == 0).count(), 255);
+    assert_eq!(sm.stats().retired, 1);
+    assert_eq!(sm.free_slots().collect::<Vec<u8>>(), vec![1]);
+
+    for key in issued.iter() {
+      assert!(sm.get(*key).is_none());
+    }
+
+    let retired: TinyKey = super::KeyData { idx: 0, gen: 3 }.into();
+    assert_eq!(sm.insert_at_key(retired, 0), Err(0));
+  }
+
+  #[test]
+  fn all_live () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..6);
+
+    assert!(sm.all_live(&keys));
+    assert!(sm.all_live(&[]));
+
+    sm.remove(keys[3]);
+
+    assert!(!sm.all_live(&keys));
+    assert!(sm.all_live(&keys[..3]));
+  }
+
+  #[test]
+  fn insert_scoped () {
+    let mut sm: super::SlotMap<super::DefaultKey, usize> = super::SlotMap::new();
+
+    let dropped = {
+      let scoped = sm.insert_scoped(1);
+      scoped.key()
+    };
+
+    assert!(!sm.contains_key(dropped));
+    assert!(sm.is_empty());
+
+    let leaked = sm.insert_scoped(2).leak();
+
+    assert_eq!(sm.get(leaked), Some(&2));
+    assert_eq!(sm.len(), 1);
+  }
+
+  #[test]
+  fn all_keys_for () {
+    let mut sm: super::SlotMap<super::DefaultKey, &str> = super::SlotMap::new();
+
+    let a0 = sm.insert("a");
+    sm.insert("b");
+    let a1 = sm.insert("a");
+
+    assert_eq!(sm.all_keys_for(&"a").collect::<Vec<_>>(), vec![a0, a1]);
+    assert_eq!(sm.find_key(&"a"), Some(a0));
+    assert_eq!(sm.all_keys_for(&"c").count(), 0);
+  }
+
+  #[test]
+  fn collect_keys () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..6);
+
+    let snapshot = sm.collect_keys();
+    assert_eq!(snapshot, keys);
+
+    for key in snapshot {
+      assert!(sm.remove(key).is_some());
+    }
+
+    assert!(sm.is_empty());
+  }
+
+  #[test]
+  fn reset_generations () {
+    let (mut sm, mut keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..6);
+
+    for _ in 0..3 {
+      sm.remove(keys[1]);
+      sm.remove(keys[4]);
+      keys[1] = sm.insert(1);
+      keys[4] = sm.insert(4);
+    }
+
+    let stale = sm.insert(6);
+    sm.remove(stale);
+
+    assert!(keys[1].gen > 0 && keys[4].gen > 0);
+
+    let remap = sm.reset_generations();
+
+    assert!(sm.check_consistency().is_ok());
+    assert!(remap.get(stale).is_none());
+
+    for (value, &old_key) in keys.iter().enumerate() {
+      let new_key = remap.get(old_key).unwrap();
+
+      assert_eq!(new_key.gen, 0);
+      assert_eq!(sm.get(new_key), Some(&value));
+    }
+
+    assert!(!sm.contains_key(keys[1]));
+    assert!(!sm.contains_key(keys[4]));
+
+    let k = sm.insert(7);
+    assert_eq!(sm[k], 7);
+    assert!(sm.check_consistency().is_ok());
+  }
+
+  #[test]
+  fn reset_generations_free_slots () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..4);
+
+    sm.remove(keys[0]);
+    sm.remove(keys[2]);
+
+    sm.reset_generations();
+
+    // keys issued for slots which were free during the reset must not match keys issued before it
+    let k0 = sm.insert(10);
+    let k2 = sm.insert(12);
+
+    assert_eq!((k0.idx, k2.idx), (keys[0].idx, keys[2].idx));
+    assert!(k0 != keys[0] && k2 != keys[2]);
+    assert!(sm.get(keys[0]).is_none());
+    assert!(sm.get(keys[2]).is_none());
+    assert_eq!((sm[k0], sm[k2]), (10, 12));
+    assert!(sm.check_consistency().is_ok());
+  }
+
+  #[test]
+  fn remove_many () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..6);
+
+    sm.remove(keys[3]);
+
+    // removing keys[0] swaps the value of keys[5] into its place before keys[5] is removed
+    let removed = sm.remove_many(vec![keys[0], keys[3], keys[5], keys[0], keys[2]]);
+
+    assert_eq!(removed, vec![Some(0), None, Some(5), None, Some(2)]);
+    assert_eq!(sm.len(), 2);
+    assert_eq!(sm[keys[1]], 1);
+    assert_eq!(sm[keys[4]], 4);
+    assert!(sm.check_consistency().is_ok());
+  }
+
+  #[test]
+  fn set () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, &str>::from_values(vec!["a", "b"]);
+
+    assert_eq!(sm.set(keys[0], "c"), Some("a"));
+    assert_eq!(sm[keys[0]], "c");
+
+    sm.remove(keys[1]);
+
+    assert_eq!(sm.set(keys[1], "d"), None);
+    assert_eq!(sm.len(), 1);
+    assert!(sm.find_key(&"d").is_none());
+  }
+
+  #[test]
+  fn first_last_key () {
+    let mut sm: super::SlotMap<super::DefaultKey, usize> = super::SlotMap::new();
+
+    assert_eq!(sm.first_key(), None);
+    assert_eq!(sm.last_key(), None);
+
+    let first = sm.insert(0);
+    assert_eq!(sm.first_key(), Some(first));
+    assert_eq!(sm.last_key(), Some(first));
+
+    sm.insert(1);
+    let last = sm.insert(2);
+
+    assert_eq!(sm.first_key(), Some(first));
+    assert_eq!(sm.last_key(), Some(last));
+  }
+
+  #[test]
+  fn version () {
+    let mut sm: super::SlotMap<super::DefaultKey, usize> = super::SlotMap::new();
+    assert_eq!(sm.version(), 0);
+
+    let a = sm.insert(0);
+    let v1 = sm.version();
+    assert!(v1 > 0);
+
+    let b = sm.insert_with_key(|_| 1);
+    let v2 = sm.version();
+    assert!(v2 > v1);
+
+    assert_eq!(sm.get(a), Some(&0));
+    *sm.get_mut(b).unwrap() = 2;
+    assert_eq!(sm.version(), v2);
+
+    sm.remove(a);
+    let v3 = sm.version();
+    assert!(v3 > v2);
+
+    assert!(sm.remove(a).is_none());
+    assert_eq!(sm.version(), v3);
+  }
+
+  #[test]
+  fn iter_sorted_by_key () {
+    let (mut a, a_keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..8);
+    let (mut b, b_keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..8);
+
+    for &i in &[1, 6, 3] { a.remove(a_keys[i]); }
+    for &i in &[3, 1, 6] { b.remove(b_keys[i]); }
+
+    assert_ne!(a.values(), b.values());
+
+    let a_sorted: Vec<(super::DefaultKey, usize)> = a.iter_sorted_by_key().map(|(k, &v)| (k, v)).collect();
+    let b_sorted: Vec<(super::DefaultKey, usize)> = b.iter_sorted_by_key().map(|(k, &v)| (k, v)).collect();
+
+    assert_eq!(a_sorted, b_sorted);
+    assert_eq!(a_sorted.iter().map(|&(_, v)| v).collect::<Vec<_>>(), vec![0, 2, 4, 5, 7]);
+  }
+
+  #[test]
+  fn get_validated_unchecked () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..4);
+
+    sm.remove(keys[2]);
+
+    unsafe {
+      assert_eq!(sm.get_validated_unchecked(keys[1]), Some(&1));
+      assert_eq!(sm.get_validated_unchecked(keys[2]), None);
+    }
+
+    let reused = sm.insert(5);
+    assert_eq!(reused.idx, keys[2].idx);
+
+    unsafe {
+      assert_eq!(sm.get_validated_unchecked(keys[2]), None);
+      assert_eq!(sm.get_validated_unchecked(reused), Some(&5));
+    }
+  }
+
+  #[test]
+  fn get_pair () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..4);
+
+    sm.remove(keys[0]);
+
+    for value_idx in 0..sm.len() {
+      let (key, &value) = sm.get_pair(value_idx).unwrap();
+
+      assert_eq!(key, sm.keys()[value_idx]);
+      assert_eq!(key, keys[value]);
+    }
+
+    let (key, value) = sm.get_pair_mut(0).unwrap();
+    *value = 30;
+    assert_eq!(sm[key], 30);
+
+    assert!(sm.get_pair(3).is_none());
+    assert!(sm.get_pair_mut(3).is_none());
+  }
+
+  #[test]
+  fn insert_with_key () {
+    struct Entity {
+      own_key: super::DefaultKey,
+    }
+
+    let mut sm: super::SlotMap<super::DefaultKey, Entity> = super::SlotMap::new();
+
+    let removed = sm.insert(Entity { own_key: super::DefaultKey::NULL });
+    sm.remove(removed);
+
+    let key = sm.insert_with_key(|key| Entity { own_key: key });
+
+    assert_eq!(key.idx, removed.idx);
+    assert_eq!(sm[key].own_key, key);
+    assert!(sm.check_consistency().is_ok());
+  }
+
+  #[test]
+  fn compact () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..8);
+
+    for &i in &[0, 3, 4] { sm.remove(keys[i]); }
+
+    assert_eq!(sm.stats().free, 3);
+
+    let remap = sm.compact();
+
+    assert!(sm.check_consistency().is_ok());
+    assert_eq!(sm.stats().free, 0);
+    assert_eq!(sm.stats().slots, sm.len());
+    assert_eq!(remap.len(), 5);
+
+    for &i in &[1, 2, 5, 6, 7] {
+      let new_key = remap[&keys[i]];
+
+      assert!((new_key.idx as usize) < sm.len());
+      assert_eq!(sm[new_key], i);
+    }
+
+    assert!(!remap.contains_key(&keys[3]));
+
+    let k = sm.insert(8);
+    assert_eq!(k.idx as usize, 5);
+    assert!(sm.check_consistency().is_ok());
+  }
+
+  #[test]
+  fn compact_stale_free_slot () {
+    let mut sm: super::SlotMap<super::DefaultKey, usize> = super::SlotMap::new();
+
+    let a = sm.insert(0);
+    sm.remove(a);
+    let b = sm.insert(1);
+    let c = sm.insert(2);
+    sm.remove(b);
+
+    let remap = sm.compact();
+    let new_c = remap[&c];
+    assert_eq!(new_c.idx, b.idx);
+
+    assert_eq!(sm.remove(new_c), Some(2));
+
+    // the freed slot's generation has advanced past that of the stale key
+    assert!(!sm.contains_key(b));
+    assert!(sm.get(b).is_none());
+    assert!(sm.get_mut(b).is_none());
+    assert!(sm.remove(b).is_none());
+    assert!(sm.check_consistency().is_ok());
+  }
+
+  #[test]
+  fn compact_invalidates_stale_keys () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..8);
+
+    for &i in &[0, 3, 4] { sm.remove(keys[i]); }
+
+    let remap = sm.compact();
+
+    for &i in &[1, 2, 5, 6, 7] {
+      assert!(remap[&keys[i]].gen > keys[i].gen);
+    }
+
+    // recreate the slots discarded by compaction
+    let fresh: Vec<_> = (8..11).map(|i| sm.insert(i)).collect();
+    assert_eq!(sm.stats().slots, 8);
+
+    for &key in keys.iter() {
+      assert!(!sm.contains_key(key), "stale key {:?} was accepted", key);
+    }
+
+    for (i, &key) in fresh.iter().enumerate() {
+      assert_eq!(sm[key], i + 8);
+    }
+
+    assert!(sm.check_consistency().is_ok());
+  }
+
+  #[test]
+  fn compact_skips_retired_slots () {
+    let mut sm: super::SlotMap<TinyKey, usize> = super::SlotMap::new();
+
+    while sm.stats().retired == 0 {
+      let key = sm.insert(0);
+      sm.remove(key);
+    }
+
+    let a = sm.insert(1);
+    let b = sm.insert(2);
+    assert_eq!((a.idx, b.idx), (1, 2));
+
+    let remap = sm.compact();
+
+    assert_eq!(sm.stats().retired, 1);
+    assert_eq!(sm.stats().slots, 3);
+    assert_eq!(sm[remap[&a]], 1);
+    assert_eq!(sm[remap[&b]], 2);
+    assert!(!sm.contains_key(a) && !sm.contains_key(b));
+    assert!(sm.check_consistency().is_ok());
+  }
+
+  #[test]
+  fn pair_iter_mut_sorted () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..8);
+
+    for &i in &[2, 0, 5] { sm.remove(keys[i]); }
+
+    let mut visited = Vec::new();
+
+    for (key, value) in sm.pair_iter_mut_sorted() {
+      visited.push(key);
+      *value *= 10;
+    }
+
+    let mut expected = visited.clone();
+    expected.sort_unstable();
+    assert_eq!(visited, expected);
+    assert_eq!(visited, vec![keys[1], keys[3], keys[4], keys[6], keys[7]]);
+
+    for &i in &[1, 3, 4, 6, 7] {
+      assert_eq!(sm[keys[i]], i * 10);
+    }
+  }
+
+  #[test]
+  fn reset_generations_stale_free_slot () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..4);
+
+    // a key held before the reset, with a generation the slot of keys[3] will have once it is freed after the reset
+    sm.remove(keys[3]);
+    let stale = sm.insert(3);
+    assert_eq!(stale.gen, 1);
+    sm.remove(stale);
+    let live = sm.insert(3);
+
+    sm.remove(keys[1]);
+    sm.remove(keys[2]);
+
+    let remap = sm.reset_generations();
+
+    let free: super::DefaultKey = super::KeyData { idx: 1, gen: 0 }.into();
+
+    assert!(!sm.contains_key(free));
+    assert!(sm.get(free).is_none());
+    assert!(sm.get_mut(free).is_none());
+    assert!(unsafe { sm.get_validated_unchecked(free) }.is_none());
+    assert!(sm.remove(free).is_none());
+
+    sm.remove(remap.get(live).unwrap());
+
+    assert!(!sm.contains_key(stale));
+    assert!(sm.get(stale).is_none());
+    assert!(sm.remove(stale).is_none());
+    assert!(sm.check_consistency().is_ok());
+  }
+
+  #[test]
+  fn map_values () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..6);
+
+    sm.remove(keys[2]);
+
+    let mut mapped = sm.map_values(|v| format!("#{}", v));
+
+    assert_eq!(mapped.len(), 5);
+    assert_eq!(mapped[keys[4]], "#4");
+    assert!(mapped.get(keys[2]).is_none());
+
+    let k = mapped.insert("new".to_owned());
+    assert_eq!(k, sm.insert(6));
+  }
+
+  #[test]
+  fn into_keys_pairs () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..6);
+
+    sm.remove(keys[0]);
+
+    assert_eq!(sm.clone().into_keys().count(), 5);
+
+    let pairs: Vec<(super::DefaultKey, usize)> = sm.into_pairs().collect();
+
+    assert_eq!(pairs.len(), 5);
+
+    for (key, value) in pairs {
+      assert_eq!(keys[value], key);
+    }
+  }
+
+  #[test]
+  fn check_consistency () {
+    let mut sm: super::SlotMap<super::DefaultKey, u64> = super::SlotMap::with_capacity(0);
+    let mut keys = Vec::new();
+
+    // simple deterministic LCG, so failures are reproducible
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = move || { state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407); state >> 33 };
+
+    for i in 0..2000 {
+      let r = next();
+
+      if r % 3 != 0 || keys.is_empty() {
+        keys.push(sm.insert(i));
+      } else {
+        let key = keys.swap_remove((r as usize / 3) % keys.len());
+        assert!(sm.remove(key).is_some());
+      }
+
+      assert_eq!(sm.check_consistency(), Ok(()));
+    }
+
+    assert_eq!(sm.len(), keys.len());
+    assert!(sm.all_live(&keys));
+
+    let slot_idx = keys[0].idx as usize;
+    sm.slots[slot_idx].gen += 1;
+
+    assert!(sm.check_consistency().is_err());
+  }
+
+  #[test]
+  fn chunks_mut () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..10);
+
+    let chunks: Vec<&mut [usize]> = sm.par_chunks_mut(4).collect();
+
+    assert_eq!(chunks.iter().map(|chunk| chunk.len()).collect::<Vec<_>>(), vec![4, 4, 2]);
+
+    for chunk in chunks {
+      for value in chunk.iter_mut() { *value *= 2 }
+    }
+
+    for (chunk_keys, chunk_values) in sm.chunks_with_keys_mut(3) {
+      assert_eq!(chunk_keys.len(), chunk_values.len());
+
+      for (key, value) in chunk_keys.iter().zip(chunk_values.iter_mut()) {
+        assert_eq!(keys[*value / 2], *key);
+        *value += 1;
+      }
+    }
+
+    for (i, key) in keys.iter().enumerate() {
+      assert_eq!(sm[*key], i * 2 + 1);
+    }
+  }
+
+  #[test]
+  fn slot_iter () {
+    let (mut sm, keys) = super::SlotMap::<super::DefaultKey, usize>::from_values(0..6);
+
+    sm.remove(keys[1]);
+    sm.remove(keys[4]);
+
+    let slots: Vec<(u32, Option<super::DefaultKey>)> = sm.slot_iter().collect();
+
+    assert_eq!(slots.len(), 6);
+
+    for (slot_idx, key) in slots {
+      if slot_idx == 1 || slot_idx == 4 {
+        assert_eq!(key, None);
+      } else {
+        assert_eq!(key, Some(keys[slot_idx as usize]));
+      }
     }
   }
 }
@@ -8,4 +8,92 @@ pub fn count_digits (mut n: usize, base: usize) -> usize {
     
     if n == 0 { break d }
   }
+}
+
+/// Determine how many digits it takes to represent a number `n` in base 10
+/// 
+/// This gives the same result as `count_digits(n, 10)`,
+/// but estimates the digit count from the bit length of `n` and corrects it with a single table lookup,
+/// rather than dividing repeatedly
+pub fn count_digits_base10 (n: usize) -> usize {
+  // the first entry is 0 rather than 1, so that 0 is counted as a single digit
+  const POWERS_OF_10: [u64; 20] = [
+    0, 10, 100, 1_000, 10_000, 100_000, 1_000_000, 10_000_000, 100_000_000, 1_000_000_000,
+    10_000_000_000, 100_000_000_000, 1_000_000_000_000, 10_000_000_000_000, 100_000_000_000_000,
+    1_000_000_000_000_000, 10_000_000_000_000_000, 100_000_000_000_000_000, 1_000_000_000_000_000_000,
+    10_000_000_000_000_000_000,
+  ];
+
+  let n = n as u64;
+  let bits = 64 - (n | 1).leading_zeros() as usize;
+
+  // 1233 / 4096 approximates log10(2), so this is floor(log10(2 ^ bits)), at most one less than the result
+  let approx = (bits * 1233) >> 12;
+
+  approx + (n >= POWERS_OF_10[approx]) as usize
+}
+
+/// Determine how many digits are needed to store a number `n` in the given `base`
+/// 
+/// Unlike `count_digits`, this is 0 when `n` is 0
+pub fn significant_digits (n: usize, base: usize) -> usize {
+  if n == 0 { 0 } else { count_digits(n, base) }
+}
+
+
+/// Determine how many characters it takes to represent a number `n` in the given `base`,
+/// with a separator of width `sep_width` between every `group` digits
+/// 
+/// E.g. `count_digits_grouped(1000000, 10, 3, 1)` is 9, for `1,000,000`
+/// 
+/// A `group` of 0 disables separators
+pub fn count_digits_grouped (n: usize, base: usize, group: usize, sep_width: usize) -> usize {
+  let d = count_digits(n, base);
+
+  if group == 0 { return d }
+
+  d + (d - 1) / group * sep_width
+}
+
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn significant () {
+    assert_eq!(significant_digits(0, 2), 0);
+    assert_eq!(significant_digits(1, 2), 1);
+    assert_eq!(significant_digits(255, 2), 8);
+    assert_eq!(count_digits(0, 2), 1);
+  }
+
+  #[test]
+  fn base10 () {
+    let mut values = vec![0, 1, 9, 10, 11, 99, 100, 101, 999, 1000, usize::MAX, usize::MAX - 1];
+
+    let mut power = 1usize;
+    while let Some(next) = power.checked_mul(10) {
+      values.extend_from_slice(&[power - 1, power, power + 1]);
+      power = next;
+    }
+
+    values.extend((0..10_000).map(|i| i * 7919));
+
+    for n in values {
+      assert_eq!(count_digits_base10(n), count_digits(n, 10), "digit count mismatch for {}", n);
+    }
+  }
+
+  #[test]
+  fn grouped () {
+    assert_eq!(count_digits_grouped(0, 10, 3, 1), 1);
+    assert_eq!(count_digits_grouped(999, 10, 3, 1), 3);
+    assert_eq!(count_digits_grouped(1000, 10, 3, 1), 5);
+    assert_eq!(count_digits_grouped(1000000, 10, 3, 1), 9);
+    assert_eq!(count_digits_grouped(1000000, 10, 3, 2), 11);
+    assert_eq!(count_digits_grouped(12345678, 10, 4, 1), 9);
+    assert_eq!(count_digits_grouped(0xffff_ffff, 16, 4, 1), 9);
+    assert_eq!(count_digits_grouped(1000000, 10, 0, 1), 7);
+  }
 }
\ No newline at end of file
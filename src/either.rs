@@ -1,3 +1,5 @@
+use std::fmt;
+
 /// A wrapper for a value that is either one type or another
 /// 
 /// Similar to Result, but without the semantic connotations
@@ -41,6 +43,61 @@ impl<A, B> Either<A,B> {
       _ => None
     }
   }
+
+
+  /// Convert an Either<A, B> to an A, using `A::default()` if it is the B variant
+  pub fn a_or_default (self) -> A
+  where A: Default
+  {
+    match self {
+      Self::A(a) => a,
+      _ => A::default()
+    }
+  }
+
+  /// Convert an Either<A, B> to a B, using `B::default()` if it is the A variant
+  pub fn b_or_default (self) -> B
+  where B: Default
+  {
+    match self {
+      Self::B(b) => b,
+      _ => B::default()
+    }
+  }
+
+
+  /// Apply a function to the A variant of an Either, producing a new Either,
+  /// or pass along the B variant untouched
+  /// 
+  /// This is the Either equivalent of `Result::and_then`, treating A as the continuing side
+  pub fn and_then_a<C, F: FnOnce(A) -> Either<C, B>> (self, f: F) -> Either<C, B> {
+    match self {
+      Self::A(a) => f(a),
+      Self::B(b) => Either::B(b)
+    }
+  }
+
+
+  /// Pair the values of two Eithers of the same variant,
+  /// or get None if they are different variants
+  pub fn zip<C, D> (self, other: Either<C, D>) -> Option<Either<(A, C), (B, D)>> {
+    match (self, other) {
+      (Self::A(a), Either::A(c)) => Some(Either::A((a, c))),
+      (Self::B(b), Either::B(d)) => Some(Either::B((b, d))),
+      _ => None
+    }
+  }
+}
+
+impl<A, B, C> Either<(A, C), (B, C)> {
+  /// Factor out a component common to both variants of an Either,
+  /// converting an `Either<(A, C), (B, C)>` into an `(Either<A, B>, C)`
+  pub fn factor_second (self) -> (Either<A, B>, C) {
+    match self {
+      Self::A((a, c)) => (Either::A(a), c),
+      Self::B((b, c)) => (Either::B(b), c)
+    }
+  }
 }
 
 /// Allows converting a value into some side of an Either
@@ -56,4 +113,142 @@ pub trait IntoEither: Sized {
   }
 }
 
-impl<T> IntoEither for T { }
\ No newline at end of file
+impl<T> IntoEither for T { }
+
+
+/// Converts `Ok` to `Either::A` and `Err` to `Either::B`
+impl<A, B> From<Result<A, B>> for Either<A, B> {
+  #[inline]
+  fn from (res: Result<A, B>) -> Self {
+    match res {
+      Ok(a) => Self::A(a),
+      Err(b) => Self::B(b)
+    }
+  }
+}
+
+/// Converts `Either::A` to `Ok` and `Either::B` to `Err`
+impl<A, B> From<Either<A, B>> for Result<A, B> {
+  #[inline]
+  fn from (either: Either<A, B>) -> Self {
+    match either {
+      Either::A(a) => Ok(a),
+      Either::B(b) => Err(b)
+    }
+  }
+}
+
+
+impl<A, B> fmt::Display for Either<A, B>
+where A: fmt::Display,
+      B: fmt::Display
+{
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::A(a) => a.fmt(f),
+      Self::B(b) => b.fmt(f)
+    }
+  }
+}
+
+
+/// Split an iterator of Eithers into a Vec of the A values and a Vec of the B values,
+/// each preserving iteration order
+pub fn partition_either<I, A, B> (iter: I) -> (Vec<A>, Vec<B>)
+where I: IntoIterator<Item = Either<A, B>>
+{
+  let mut a_values = Vec::new();
+  let mut b_values = Vec::new();
+
+  for e in iter {
+    match e {
+      Either::A(a) => a_values.push(a),
+      Either::B(b) => b_values.push(b)
+    }
+  }
+
+  (a_values, b_values)
+}
+
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn and_then_a () {
+    let halve = |n: i32| if n % 2 == 0 { Either::A(n / 2) } else { Either::B(format!("{} is odd", n)) };
+
+    assert_eq!(Either::A(8).and_then_a(halve).and_then_a(halve), Either::A(2));
+    assert_eq!(Either::A(6).and_then_a(halve).and_then_a(halve), Either::B("3 is odd".to_owned()));
+    assert_eq!(Either::A(5).and_then_a(halve).and_then_a(|_| -> Either<i32, String> { unreachable!() }), Either::B("5 is odd".to_owned()));
+  }
+
+  #[test]
+  fn zip () {
+    let a0: Either<i32, char> = Either::A(1);
+    let a1: Either<&str, bool> = Either::A("one");
+    let b0: Either<i32, char> = Either::B('x');
+    let b1: Either<&str, bool> = Either::B(true);
+
+    assert_eq!(a0.zip(a1), Some(Either::A((1, "one"))));
+    assert_eq!(b0.zip(b1), Some(Either::B(('x', true))));
+    assert_eq!(a0.zip(b1), None);
+    assert_eq!(b0.zip(a1), None);
+  }
+
+  #[test]
+  fn or_default () {
+    let a: Either<i32, String> = Either::A(5);
+    let b: Either<i32, String> = Either::B("missing".to_owned());
+
+    assert_eq!(a.clone().a_or_default(), 5);
+    assert_eq!(b.clone().a_or_default(), 0);
+    assert_eq!(a.b_or_default(), "");
+    assert_eq!(b.b_or_default(), "missing");
+  }
+
+  #[test]
+  fn result_conversion () {
+    let ok: Result<i32, String> = Ok(1);
+    let err: Result<i32, String> = Err("failed".to_owned());
+
+    let a: Either<i32, String> = ok.into();
+    let b: Either<i32, String> = err.into();
+
+    assert_eq!(a, Either::A(1));
+    assert_eq!(b, Either::B("failed".to_owned()));
+
+    let ok: Result<i32, String> = a.into();
+    let err: Result<i32, String> = b.into();
+
+    assert_eq!(ok, Ok(1));
+    assert_eq!(err, Err("failed".to_owned()));
+  }
+
+  #[test]
+  fn display () {
+    let a: Either<i32, &str> = Either::A(12);
+    let b: Either<i32, &str> = Either::B("twelve");
+
+    assert_eq!(a.to_string(), "12");
+    assert_eq!(b.to_string(), "twelve");
+    assert_eq!(format!("{:>4}", a), "  12");
+  }
+
+  #[test]
+  fn partition () {
+    let mixed = vec![Either::A(1), Either::B('x'), Either::A(2), Either::B('y'), Either::A(3)];
+
+    assert_eq!(partition_either(mixed), (vec![1, 2, 3], vec!['x', 'y']));
+  }
+
+  #[test]
+  fn factor_second () {
+    let a: Either<(i32, &str), (char, &str)> = Either::A((1, "ctx"));
+    let b: Either<(i32, &str), (char, &str)> = Either::B(('x', "ctx"));
+
+    assert_eq!(a.factor_second(), (Either::A(1), "ctx"));
+    assert_eq!(b.factor_second(), (Either::B('x'), "ctx"));
+  }
+}
\ No newline at end of file
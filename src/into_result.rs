@@ -1,3 +1,5 @@
+use crate::Either;
+
 /// Allows the creation of a Result with a custom Err from a container type
 pub trait IntoResult {
   /// The inner value type of the container, which becomes the Ok variant of Result when `into_result` is called
@@ -21,4 +23,36 @@ impl<T> IntoResult for Option<T> {
       Err(if_none())
     }
   }
+}
+
+/// Converts Either::A to Result::Ok,
+/// and Either::B to a Result::Err created by `if_none`
+/// 
+/// Note that the B value is discarded, as the Err type is chosen by the caller
+impl<A, B> IntoResult for Either<A, B> {
+  type Value = A;
+
+  fn into_result<E, F> (self, if_none: F) -> Result<Self::Value, E>
+  where F: FnOnce() -> E
+  {
+    match self {
+      Either::A(a) => Ok(a),
+      Either::B(_) => Err(if_none())
+    }
+  }
+}
+
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn either () {
+    let a: Either<i32, &str> = Either::A(1);
+    let b: Either<i32, &str> = Either::B("b");
+
+    assert_eq!(a.into_result(|| "none"), Ok(1));
+    assert_eq!(b.into_result(|| "none"), Err("none"));
+  }
 }
\ No newline at end of file
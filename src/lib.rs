@@ -42,10 +42,10 @@ mod into_result;
 pub use into_result::IntoResult;
 
 mod count_digits;
-pub use count_digits::count_digits;
+pub use count_digits::{ count_digits, count_digits_base10, count_digits_grouped, significant_digits };
 
 mod padding;
-pub use padding::padding;
+pub use padding::{ padding, Padding };
 
 mod write_adaptor;
 pub use write_adaptor::*;
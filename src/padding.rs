@@ -1,6 +1,38 @@
+use std::fmt;
+
 /// Get a str filled with a given number of spaces, up to 256
 pub fn padding (n: u8) -> &'static str {
   const SPACES: [u8; 256] = [b' '; 256];
 
   unsafe { std::str::from_utf8_unchecked(std::slice::from_raw_parts(SPACES.as_ptr(), n as _)) }
+}
+
+
+/// A Display type which writes a fill character a given number of times
+/// 
+/// Unlike `padding`, this has no length limit and can use any character
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Padding(pub usize, pub char);
+
+impl fmt::Display for Padding {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for _ in 0..self.0 { fmt::Write::write_char(f, self.1)?; }
+    Ok(())
+  }
+}
+
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn padding_display () {
+    let s = format!("{}", Padding(300, '.'));
+
+    assert_eq!(s.len(), 300);
+    assert!(s.chars().all(|c| c == '.'));
+
+    assert_eq!(format!("[{}]", Padding(2, '❤')), "[❤❤]");
+  }
 }
\ No newline at end of file
@@ -6,6 +6,25 @@ pub trait Reduce: Iterator {
   /// before the callback is called
   fn reduce<F> (self, f: F) -> Self::Item
   where F: Fn(Self::Item, Self::Item) -> Self::Item;
+
+  /// Iterator reduction over borrowed items, where the accumulator
+  /// is initialized with the first reference of the iterator
+  /// before the callback is called
+  /// 
+  /// Returns None if the iterator is empty
+  fn reduce_ref<'a, T: 'a + ?Sized, F> (self, f: F) -> Option<&'a T>
+  where Self: Iterator<Item = &'a T>,
+        F: Fn(&'a T, &'a T) -> &'a T;
+
+  /// Iterator reduction where the accumulator is initialized with the first value of the iterator,
+  /// which stops early when the callback returns None, yielding the accumulator so far
+  /// 
+  /// The accumulator is passed to the callback by reference,
+  /// so that it is still available to be returned if the callback stops the reduction
+  /// 
+  /// Returns None if the iterator is empty
+  fn reduce_while<F> (self, f: F) -> Option<Self::Item>
+  where F: FnMut(&Self::Item, Self::Item) -> Option<Self::Item>;
 }
 
 impl<T> Reduce for T
@@ -23,4 +42,65 @@ where T: Iterator
 
     acc
   }
+
+  #[inline]
+  fn reduce_ref<'a, U: 'a + ?Sized, F> (mut self, f: F) -> Option<&'a U>
+  where Self: Iterator<Item = &'a U>,
+        F: Fn(&'a U, &'a U) -> &'a U
+  {
+    let mut acc = self.next()?;
+
+    for e in self {
+      acc = f(acc, e);
+    }
+
+    Some(acc)
+  }
+
+  #[inline]
+  fn reduce_while<F> (mut self, mut f: F) -> Option<Self::Item>
+  where F: FnMut(&Self::Item, Self::Item) -> Option<Self::Item>
+  {
+    let mut acc = self.next()?;
+
+    for e in self {
+      match f(&acc, e) {
+        Some(next) => acc = next,
+        None => break
+      }
+    }
+
+    Some(acc)
+  }
+}
+
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn reduce_ref () {
+    let values = [3, 9, 2, 7];
+
+    let max = values.iter().reduce_ref(|a, b| if b > a { b } else { a });
+    assert_eq!(max, Some(&values[1]));
+    assert!(std::ptr::eq(max.unwrap(), &values[1]));
+
+    let empty: [i32; 0] = [];
+    assert_eq!(empty.iter().reduce_ref(|a, _| a), None);
+  }
+
+  #[test]
+  fn reduce_while () {
+    let mut visited = 0;
+
+    let sum = (1..10).inspect(|_| visited += 1).reduce_while(|acc, e| if acc + e > 10 { None } else { Some(acc + e) });
+
+    assert_eq!(sum, Some(10));
+    assert_eq!(visited, 5);
+
+    assert_eq!((1..5).reduce_while(|acc, e| Some(acc + e)), Some(10));
+    assert_eq!(std::iter::empty::<i32>().reduce_while(|acc, e| Some(acc + e)), None);
+  }
 }
\ No newline at end of file
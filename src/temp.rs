@@ -2,15 +2,41 @@
 
 use std::{
   thread_local,
+  thread::LocalKey,
   cell::RefCell,
   mem::{
     transmute,
     size_of,
+    align_of,
+    take,
+  },
+  ops::{
+    Deref,
+    DerefMut,
+  },
+  ptr::{
+    read,
+    write,
+    copy_nonoverlapping,
+    drop_in_place,
+  },
+  slice::{
+    from_raw_parts as make_slice_ref,
+    from_raw_parts_mut as make_slice,
   },
-  slice::from_raw_parts_mut as make_slice
 };
 
-use super::{ unescape_str_into, escape_str_into };
+use super::{ unescape_str_into, escape_str_into, escape_str_minimal_into };
+
+
+thread_local! {
+  static BUFF: RefCell<Vec<u8>> = RefCell::new(Vec::new());
+  static UE_BUFF: RefCell<String> = RefCell::new(String::new());
+  static E_BUFF: RefCell<String> = RefCell::new(String::new());
+  static EM_BUFF: RefCell<String> = RefCell::new(String::new());
+  static LC_BUFF: RefCell<String> = RefCell::new(String::new());
+  static UC_BUFF: RefCell<String> = RefCell::new(String::new());
+}
 
 
 /// Static allocating function for getting a temporary buffer of any type,
@@ -23,10 +49,6 @@ use super::{ unescape_str_into, escape_str_into };
 /// 
 /// Resulting buffer is only valid until the next call of this function on this thread for this type
 pub unsafe fn buffer<T> (size: usize) -> &'static mut [T] {
-  thread_local! {
-    static BUFF: RefCell<Vec<u8>> = RefCell::new(Vec::new());
-  }
-
   BUFF.with(|rc| {
     let mut buff = rc.borrow_mut();
 
@@ -37,14 +59,130 @@ pub unsafe fn buffer<T> (size: usize) -> &'static mut [T] {
 }
 
 
+thread_local! {
+  static TV_BUFF: RefCell<Vec<u8>> = RefCell::new(Vec::new());
+}
+
+/// A growable array of values stored in a thread local temporary buffer,
+/// obtained by calling `temp::vec`
+/// 
+/// Unlike `temp::buffer`, the values in a TempVec are dropped when it is dropped,
+/// after which the buffer is returned for reuse by the next TempVec on this thread
+pub struct TempVec<T> {
+  buff: Vec<u8>,
+  offset: usize,
+  len: usize,
+  cap: usize,
+  phantom: std::marker::PhantomData<T>,
+}
+
+/// Static allocating function for getting an empty TempVec,
+/// which uses a thread local buffer for its storage
+/// 
+/// The buffer is held by the TempVec until it is dropped;
+/// creating another TempVec before then simply uses a new buffer
+pub fn vec<T> () -> TempVec<T> {
+  let buff = TV_BUFF.with(|rc| take(&mut *rc.borrow_mut()));
+
+  TempVec::from_buff(buff)
+}
+
+impl<T> TempVec<T> {
+  fn from_buff (buff: Vec<u8>) -> Self {
+    let offset = buff.as_ptr().align_offset(align_of::<T>());
+
+    let cap =
+      if size_of::<T>() == 0 { usize::MAX }
+      else if buff.capacity() <= offset { 0 }
+      else { (buff.capacity() - offset) / size_of::<T>() };
+
+    Self { buff, offset, len: 0, cap, phantom: std::marker::PhantomData }
+  }
+
+  #[inline]
+  fn base_ptr (&self) -> *const T {
+    unsafe { self.buff.as_ptr().add(self.offset) as *const T }
+  }
+
+  #[inline]
+  fn base_ptr_mut (&mut self) -> *mut T {
+    unsafe { self.buff.as_mut_ptr().add(self.offset) as *mut T }
+  }
+
+  fn grow (&mut self) {
+    let new_cap = (self.cap * 2).max(4);
+
+    let new_size = new_cap.checked_mul(size_of::<T>())
+      .and_then(|size| size.checked_add(align_of::<T>() - 1))
+      .expect("TempVec capacity overflow");
+
+    let mut new = Self::from_buff(Vec::with_capacity(new_size));
+
+    unsafe { copy_nonoverlapping(self.base_ptr(), new.base_ptr_mut(), self.len) }
+
+    new.len = self.len;
+    self.len = 0;
+
+    *self = new;
+  }
+
+  /// Add a value to the end of a TempVec, growing its buffer if necessary
+  pub fn push (&mut self, value: T) {
+    if self.len == self.cap { self.grow() }
+
+    unsafe { write(self.base_ptr_mut().add(self.len), value) }
+
+    self.len += 1;
+  }
+
+  /// Remove the value at the end of a TempVec, if it is not empty
+  pub fn pop (&mut self) -> Option<T> {
+    if self.len > 0 {
+      self.len -= 1;
+
+      Some(unsafe { read(self.base_ptr().add(self.len)) })
+    } else {
+      None
+    }
+  }
+}
+
+impl<T> Deref for TempVec<T> {
+  type Target = [T];
+
+  #[inline]
+  fn deref (&self) -> &Self::Target {
+    unsafe { make_slice_ref(self.base_ptr(), self.len) }
+  }
+}
+
+impl<T> DerefMut for TempVec<T> {
+  #[inline]
+  fn deref_mut (&mut self) -> &mut Self::Target {
+    unsafe { make_slice(self.base_ptr_mut(), self.len) }
+  }
+}
+
+impl<T> Drop for TempVec<T> {
+  fn drop (&mut self) {
+    unsafe { drop_in_place(&mut **self as *mut [T]) }
+
+    let buff = take(&mut self.buff);
+
+    // keep whichever buffer is larger, in case another TempVec was created while this one was alive
+    let _ = TV_BUFF.try_with(|rc| {
+      let mut own = rc.borrow_mut();
+
+      if buff.capacity() > own.capacity() { *own = buff }
+    });
+  }
+}
+
+
 /// Static allocating function for getting a temporary version of a string with `util::unescape_str_into` applied to it
 /// 
 /// Resulting str is only valid until the next call of this function on this thread
 pub fn unescape_str (s: &str) -> &'static str {
-  thread_local! {
-    static UE_BUFF: RefCell<String> = RefCell::new(String::new());
-  }
-
   UE_BUFF.with(|rc| {
     let mut buff = rc.borrow_mut();
 
@@ -62,10 +200,6 @@ pub fn unescape_str (s: &str) -> &'static str {
 /// 
 /// Resulting str is only valid until the next call of this function on this thread
 pub fn escape_str (s: &str) -> &'static str {
-  thread_local! {
-    static E_BUFF: RefCell<String> = RefCell::new(String::new());
-  }
-
   E_BUFF.with(|rc| {
     let mut buff = rc.borrow_mut();
 
@@ -77,22 +211,42 @@ pub fn escape_str (s: &str) -> &'static str {
   })
 }
 
+/// Static allocating function for getting a temporary version of a string with `util::escape_str_minimal_into` applied to it
+/// 
+/// Unlike `escape_str`, printable unicode characters are passed through unescaped
+/// 
+/// Resulting str is only valid until the next call of this function on this thread
+pub fn escape_str_minimal (s: &str) -> &'static str {
+  EM_BUFF.with(|rc| {
+    let mut buff = rc.borrow_mut();
+
+    buff.clear();
+
+    escape_str_minimal_into(s, &mut buff);
+
+    unsafe { transmute(buff.as_str()) }
+  })
+}
+
 /// Static allocating function for getting a temporary lowercase version of a string
 /// 
+/// Strings which are entirely ASCII take a faster, byte-level path
+/// 
 /// Resulting str is only valid until the next call of this function on this thread
 pub fn to_lowercase (s: &str) -> &'static str {
-  thread_local! {
-    static LC_BUFF: RefCell<String> = RefCell::new(String::new());
-  }
-
   LC_BUFF.with(|rc| {
     let mut buff = rc.borrow_mut();
 
     buff.clear();
 
-    for c in s.chars() {
-      for lc in c.to_lowercase() {
-        buff.push(lc);
+    if s.is_ascii() {
+      buff.push_str(s);
+      buff.make_ascii_lowercase();
+    } else {
+      for c in s.chars() {
+        for lc in c.to_lowercase() {
+          buff.push(lc);
+        }
       }
     }
 
@@ -103,20 +257,23 @@ pub fn to_lowercase (s: &str) -> &'static str {
 
 /// Static allocating function for getting a temporary uppercase version of a string
 /// 
+/// Strings which are entirely ASCII take a faster, byte-level path
+/// 
 /// Resulting str is only valid until the next call of this function on this thread
 pub fn to_uppercase (s: &str) -> &'static str {
-  thread_local! {
-    static UC_BUFF: RefCell<String> = RefCell::new(String::new());
-  }
-
   UC_BUFF.with(|rc| {
     let mut buff = rc.borrow_mut();
 
     buff.clear();
 
-    for c in s.chars() {
-      for uc in c.to_uppercase() {
-        buff.push(uc);
+    if s.is_ascii() {
+      buff.push_str(s);
+      buff.make_ascii_uppercase();
+    } else {
+      for c in s.chars() {
+        for uc in c.to_uppercase() {
+          buff.push(uc);
+        }
       }
     }
 
@@ -125,6 +282,79 @@ pub fn to_uppercase (s: &str) -> &'static str {
 }
 
 
+/// Run a closure, then shrink this thread's temporary buffers back to the capacities they had before it,
+/// freeing any memory they gained during the call
+/// 
+/// This is intended for pooled threads, where buffers would otherwise keep the size of the largest task run on them
+/// 
+/// Buffers are shrunk even if the closure panics
+/// 
+/// # Safety
+/// Temporary values obtained inside the closure (e.g. the `&'static str` from `to_lowercase`)
+/// are freed when it returns, and must not escape it, including through its result
+/// 
+/// Temporary values obtained before the call must not be used after it
+pub unsafe fn with_scoped_buffers<R, F: FnOnce() -> R> (f: F) -> R {
+  let _snapshot = BufferSnapshot::take();
+
+  f()
+}
+
+
+// the snapshot arrays and buffer lists share these lengths, so a buffer cannot be added to one but not the other
+const BYTE_BUFFER_COUNT: usize = 2;
+const STRING_BUFFER_COUNT: usize = 5;
+
+struct BufferSnapshot {
+  bytes: [usize; BYTE_BUFFER_COUNT],
+  strings: [usize; STRING_BUFFER_COUNT],
+}
+
+impl BufferSnapshot {
+  fn byte_buffers () -> [&'static LocalKey<RefCell<Vec<u8>>>; BYTE_BUFFER_COUNT] {
+    [&BUFF, &TV_BUFF]
+  }
+
+  fn string_buffers () -> [&'static LocalKey<RefCell<String>>; STRING_BUFFER_COUNT] {
+    [&UE_BUFF, &E_BUFF, &EM_BUFF, &LC_BUFF, &UC_BUFF]
+  }
+
+  fn take () -> Self {
+    let mut snapshot = Self { bytes: [0; BYTE_BUFFER_COUNT], strings: [0; STRING_BUFFER_COUNT] };
+
+    for (cap, key) in snapshot.bytes.iter_mut().zip(Self::byte_buffers().iter()) {
+      *cap = key.with(|rc| rc.borrow().capacity());
+    }
+
+    for (cap, key) in snapshot.strings.iter_mut().zip(Self::string_buffers().iter()) {
+      *cap = key.with(|rc| rc.borrow().capacity());
+    }
+
+    snapshot
+  }
+}
+
+impl Drop for BufferSnapshot {
+  fn drop (&mut self) {
+    for (&cap, key) in self.bytes.iter().zip(Self::byte_buffers().iter()) {
+      let _ = key.try_with(|rc| {
+        let mut buff = rc.borrow_mut();
+
+        if buff.capacity() > cap { *buff = Vec::with_capacity(cap) }
+      });
+    }
+
+    for (&cap, key) in self.strings.iter().zip(Self::string_buffers().iter()) {
+      let _ = key.try_with(|rc| {
+        let mut buff = rc.borrow_mut();
+
+        if buff.capacity() > cap { *buff = String::with_capacity(cap) }
+      });
+    }
+  }
+}
+
+
 #[cfg(test)]
 mod test {
   #[test]
@@ -147,4 +377,85 @@ mod test {
 
     assert_eq!(main_lc, "i'm the main thread");
   }
+
+  #[test]
+  fn ascii_fast_path () {
+    use super::*;
+
+    for s in ["Hello_World_123", "ÀÉÎõü Straße", "MiXeD ascii ΑΒΓ"].iter() {
+      assert_eq!(to_lowercase(s), s.to_lowercase());
+      assert_eq!(to_uppercase(s), s.to_uppercase());
+    }
+  }
+
+  #[test]
+  fn temp_vec () {
+    use super::*;
+
+    use std::{ rc::Rc, cell::Cell };
+
+    struct Counted(String, Rc<Cell<usize>>);
+
+    impl Drop for Counted {
+      fn drop (&mut self) { self.1.set(self.1.get() + 1) }
+    }
+
+    let drops = Rc::new(Cell::new(0));
+
+    {
+      let mut tv = vec::<Counted>();
+
+      for i in 0..10 {
+        tv.push(Counted(i.to_string(), drops.clone()));
+      }
+
+      assert_eq!(tv.len(), 10);
+      assert_eq!(tv[7].0, "7");
+
+      drop(tv.pop());
+      assert_eq!(drops.get(), 1);
+    }
+
+    assert_eq!(drops.get(), 10);
+
+    let mut tv = vec::<u64>();
+
+    for i in 0..100 {
+      tv.push(i);
+    }
+
+    assert_eq!(tv.as_ptr() as usize % std::mem::align_of::<u64>(), 0);
+    assert_eq!(tv.iter().sum::<u64>(), 4950);
+  }
+
+  #[test]
+  fn scoped_buffers () {
+    use super::*;
+
+    let before = LC_BUFF.with(|rc| rc.borrow().capacity());
+    let long = "X".repeat(before + 100_000);
+
+    let len = unsafe {
+      with_scoped_buffers(|| {
+        let lc = to_lowercase(&long);
+
+        assert!(LC_BUFF.with(|rc| rc.borrow().capacity()) >= long.len());
+
+        lc.len()
+      })
+    };
+
+    assert_eq!(len, long.len());
+    assert!(LC_BUFF.with(|rc| rc.borrow().capacity()) <= before);
+
+    assert_eq!(to_lowercase("STILL WORKS"), "still works");
+  }
+
+  #[test]
+  fn escape_minimal () {
+    use super::*;
+
+    assert_eq!(escape_str_minimal("café \"ok\"\n\x01"), r#"café \"ok\"\n\u0001"#);
+    assert_eq!(escape_str("café"), r"caf\u00e9");
+  }
 }
\ No newline at end of file
@@ -1,27 +1,36 @@
 /// Extract the value of an Option::Some or return from the current context
 /// 
 /// Works similar to the try operator, but is usable in functions that return ()
+/// 
+/// The `else { ... }` form runs a block instead of returning, which is expected to diverge
 #[macro_export]
 macro_rules! some {
   ($val: expr) => { if let Some(v) = $val { v } else { return } };
+  ($val: expr ; else $else: block) => { if let Some(v) = $val { v } else $else };
   ($val: expr ; $ret: expr) => { if let Some(v) = $val { v } else { return $ret } };
 }
 
 /// Extract the value of a Result::Ok or return from the current context
 /// 
 /// Works similar to the try operator, but is usable in functions that return ()
+/// 
+/// The `else { ... }` form runs a block instead of returning, which is expected to diverge
 #[macro_export]
 macro_rules! ok {
   ($val: expr) => { if let Ok(v) = $val { v } else { return } };
+  ($val: expr ; else $else: block) => { if let Ok(v) = $val { v } else $else };
   ($val: expr ; $ret: expr) => { if let Ok(v) = $val { v } else { return $ret } };
 }
 
 /// Extract the value of a Result::Err or return from the current context
 /// 
 /// Works similar to the try operator, but is usable in functions that return ()
+/// 
+/// The `else { ... }` form runs a block instead of returning, which is expected to diverge
 #[macro_export]
 macro_rules! err {
   ($val: expr) => { if let Err(v) = $val { v } else { return } };
+  ($val: expr ; else $else: block) => { if let Err(v) = $val { v } else $else };
   ($val: expr ; $ret: expr) => { if let Err(v) = $val { v } else { return $ret } };
 }
 
@@ -32,4 +41,49 @@ macro_rules! breakable_block {
     { $($tt)* }
     unreachable!("breakable_block not broken");
   } }
+}
+
+
+#[cfg(test)]
+mod test {
+  fn first_even (values: &[i32], misses: &mut usize) -> i32 {
+    let v = some!(values.iter().find(|v| *v % 2 == 0) ; else {
+      *misses += 1;
+      return -1
+    });
+
+    *v
+  }
+
+  fn parse_or_log (s: &str, log: &mut Vec<String>) -> i32 {
+    let v = ok!(s.parse::<i32>() ; else {
+      log.push(format!("failed to parse `{}`", s));
+      return 0
+    });
+
+    let e = err!(if v < 0 { Err(v) } else { Ok(v) } ; else {
+      return v
+    });
+
+    -e
+  }
+
+  #[test]
+  fn else_form () {
+    let mut misses = 0;
+    assert_eq!(first_even(&[1, 3, 4], &mut misses), 4);
+    assert_eq!(first_even(&[1, 3, 5], &mut misses), -1);
+    assert_eq!(misses, 1);
+
+    let mut log = Vec::new();
+    assert_eq!(parse_or_log("12", &mut log), 12);
+    assert_eq!(parse_or_log("-12", &mut log), 12);
+    assert_eq!(parse_or_log("x", &mut log), 0);
+    assert_eq!(log, vec!["failed to parse `x`".to_owned()]);
+
+    for i in 0..3 {
+      let _ = some!(if i == 1 { None } else { Some(i) } ; else { continue });
+      assert_ne!(i, 1);
+    }
+  }
 }
\ No newline at end of file
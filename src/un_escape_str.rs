@@ -1,3 +1,51 @@
+use std::{ fmt, io };
+
+use crate::FMTAdaptable;
+
+
+/// Determines how unescape functions handle escape sequences they do not recognize
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnescapeMode {
+  /// Unknown escape sequences and unaccompanied trailing backslashes produce an error
+  Strict,
+  /// Unknown escape sequences are replaced with the escaped character, discarding the backslash
+  LenientDrop,
+  /// Unknown escape sequences and unaccompanied trailing backslashes are preserved verbatim
+  LenientPreserve,
+}
+
+impl Default for UnescapeMode {
+  #[inline] fn default () -> Self { Self::LenientDrop }
+}
+
+
+/// An error produced while unescaping a string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnescapeError {
+  /// An escape sequence was not recognized, in `UnescapeMode::Strict`
+  UnknownEscape(char),
+  /// The source ended with an unaccompanied backslash, in `UnescapeMode::Strict`
+  TrailingBackslash,
+  /// A `\u` escape sequence did not contain a valid unicode scalar value
+  InvalidUnicode,
+  /// The unescaped string would have been longer than the given maximum length, in bytes
+  ExceededMaxLength(usize),
+}
+
+impl fmt::Display for UnescapeError {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::UnknownEscape(ch) => write!(f, "Unknown escape sequence `\\{}`", ch),
+      Self::TrailingBackslash => write!(f, "Unaccompanied backslash at end of string"),
+      Self::InvalidUnicode => write!(f, "Invalid unicode escape sequence"),
+      Self::ExceededMaxLength(max_len) => write!(f, "Unescaped string exceeds maximum length of {} bytes", max_len),
+    }
+  }
+}
+
+impl std::error::Error for UnescapeError { }
+
+
 /// Unescape special character sequences into their literal equivalent
 /// 
 /// For example `\n` becomes a real new line character
@@ -23,34 +71,140 @@ pub fn unescape_str (source: &str) -> String {
 /// 
 /// Note that if the last char of the String is an unaccompanied backslash `\`,
 /// this is considered an invalid escape sequence and it is simply discarded
+/// 
+/// This uses `UnescapeMode::LenientDrop`, and panics if a utf escape is invalid
 pub fn unescape_str_into (source: &str, dest: &mut String) {
-  dest.reserve(source.len());
+  unescape_str_into_with_mode(source, dest, UnescapeMode::LenientDrop).expect("Failed to unescape str")
+}
+
+
+/// Unescape special character sequences into their literal equivalent,
+/// handling unknown escape sequences according to the given UnescapeMode
+/// 
+/// This version creates a new String, use `unescape_str_into_with_mode` to use an existing String
+#[inline]
+pub fn unescape_str_with_mode (source: &str, mode: UnescapeMode) -> Result<String, UnescapeError> {
+  let mut result = String::new();
+  unescape_str_into_with_mode(source, &mut result, mode)?;
+  Ok(result)
+}
+
+/// Unescape special character sequences into their literal equivalent,
+/// handling unknown escape sequences according to the given UnescapeMode
+/// 
+/// This version copies onto the end of an existing String, use `unescape_str_with_mode` to use a new String
+/// 
+/// If an error is returned, `dest` may contain a partially unescaped copy of the source
+#[inline]
+pub fn unescape_str_into_with_mode (source: &str, dest: &mut String, mode: UnescapeMode) -> Result<(), UnescapeError> {
+  unescape_impl(source, dest, mode, false, usize::MAX)
+}
+
+
+/// Unescape special character sequences into their literal equivalent,
+/// failing if the result would be longer than `max_len` bytes
+/// 
+/// This is intended for untrusted input, such as mod files,
+/// and avoids allocating more than `max_len` bytes for the result
+/// 
+/// This uses `UnescapeMode::LenientDrop`
+pub fn unescape_str_bounded (source: &str, max_len: usize) -> Result<String, UnescapeError> {
+  let mut result = String::with_capacity(source.len().min(max_len));
+  unescape_impl(source, &mut result, UnescapeMode::LenientDrop, false, max_len)?;
+  Ok(result)
+}
+
+
+/// Unescape special character sequences into their literal equivalent,
+/// handling unknown escape sequences according to the given UnescapeMode,
+/// and treating a backslash followed by a line break (`\n` or `\r\n`) as a line continuation,
+/// removing both
+/// 
+/// This version creates a new String, use `unescape_str_into_with_continuations` to use an existing String
+#[inline]
+pub fn unescape_str_with_continuations (source: &str, mode: UnescapeMode) -> Result<String, UnescapeError> {
+  let mut result = String::new();
+  unescape_str_into_with_continuations(source, &mut result, mode)?;
+  Ok(result)
+}
+
+/// Unescape special character sequences into their literal equivalent,
+/// handling unknown escape sequences according to the given UnescapeMode,
+/// and treating a backslash followed by a line break (`\n` or `\r\n`) as a line continuation,
+/// removing both
+/// 
+/// This version copies onto the end of an existing String, use `unescape_str_with_continuations` to use a new String
+/// 
+/// If an error is returned, `dest` may contain a partially unescaped copy of the source
+#[inline]
+pub fn unescape_str_into_with_continuations (source: &str, dest: &mut String, mode: UnescapeMode) -> Result<(), UnescapeError> {
+  unescape_impl(source, dest, mode, true, usize::MAX)
+}
+
+
+fn unescape_impl (source: &str, dest: &mut String, mode: UnescapeMode, line_continuation: bool, max_len: usize) -> Result<(), UnescapeError> {
+  let limit = dest.len().saturating_add(max_len);
+
+  dest.reserve(source.len().min(max_len));
 
   let mut chars = source.chars();
 
   while let Some(ch) = chars.next() {
-    dest.push(
-      if ch != '\\' {
-        ch
-      } else {
-        match chars.next() {
-          Some('u') => {
-            let value = chars.by_ref().take(4).fold(0, |acc, c| acc * 16 + c.to_digit(16).unwrap());
-            std::char::from_u32(value).unwrap()
+    let out = if ch != '\\' {
+      ch
+    } else {
+      match chars.next() {
+        Some('u') => {
+          let mut value = 0;
+
+          for c in chars.by_ref().take(4) {
+            value = value * 16 + c.to_digit(16).ok_or(UnescapeError::InvalidUnicode)?;
           }
-          Some('b') => '\x08',
-          Some('f') => '\x0c',
-          Some('n') => '\n',
-          Some('r') => '\r',
-          Some('t') => '\t',
 
-          Some(ch) => ch,
+          std::char::from_u32(value).ok_or(UnescapeError::InvalidUnicode)?
+        }
+        // \0 is never the start of an octal escape, so any digits following it are literal
+        Some('0') => '\0',
+        Some('b') => '\x08',
+        Some('f') => '\x0c',
+        Some('n') => '\n',
+        Some('r') => '\r',
+        Some('t') => '\t',
+
+        Some(ch @ '\\') | Some(ch @ '\'') | Some(ch @ '"') => ch,
+
+        Some('\n') if line_continuation => continue,
+        Some('\r') if line_continuation && chars.clone().next() == Some('\n') => {
+          chars.next();
+          continue
+        }
+
+        Some(ch) => match mode {
+          UnescapeMode::Strict => return Err(UnescapeError::UnknownEscape(ch)),
+          UnescapeMode::LenientDrop => ch,
+          UnescapeMode::LenientPreserve => {
+            if dest.len() + 1 + ch.len_utf8() > limit { return Err(UnescapeError::ExceededMaxLength(max_len)) }
+
+            dest.push('\\');
+            ch
+          }
+        },
 
-          None => return
+        None => match mode {
+          UnescapeMode::Strict => return Err(UnescapeError::TrailingBackslash),
+          UnescapeMode::LenientDrop => break,
+          UnescapeMode::LenientPreserve => '\\'
         }
       }
-    )
+    };
+
+    // checked before pushing, so that dest never grows beyond the limit
+    if dest.len() + out.len_utf8() > limit { return Err(UnescapeError::ExceededMaxLength(max_len)) }
+
+    dest.push(out);
   }
+
+  Ok(())
 }
 
 
@@ -78,29 +232,81 @@ pub fn escape_str (source: &str) -> String {
 pub fn escape_str_into (source: &str, dest: &mut String) {
   dest.reserve(source.len());
 
-  for ch in source.chars() {
-    match ch {
-      '\\' => dest.push_str("\\\\"),
-      '\x08' => dest.push_str("\\b"),
-      '\x0c' => dest.push_str("\\f"),
-      '\'' => dest.push_str("\\'"),
-      '"' => dest.push_str("\\\""),
-      '\n' => dest.push_str("\\n"),
-      '\r' => dest.push_str("\\r"),
-      '\t' => dest.push_str("\\t"),
-      '\x7f' ..= std::char::MAX => {
-        let mut esc = *b"\\u0000";
+  escape_impl(source, dest, is_del_or_non_ascii).expect("Failed to escape str")
+}
+
+/// Unescape special character sequences into their serialization-safe equivalent
+/// 
+/// For example `\n` becomes two characters, `\` followed by `n`
+/// 
+/// Utf escapes to be in the format `\uXXXX` where `X` are hex digits
+/// 
+/// This version writes directly to an io::Write, without an intermediate String;
+/// runs of characters which do not need escaping are written in a single call
+pub fn escape_str_to_io<W: io::Write> (source: &str, w: &mut W) -> io::Result<()> {
+  let mut adaptor = w.adapt_to_fmt_gen();
+
+  escape_impl(source, &mut adaptor, is_del_or_non_ascii).map_err(|_| {
+    adaptor.take_error().unwrap_or_else(|| io::Error::other("Failed to escape str"))
+  })
+}
+
 
+fn is_del_or_non_ascii (ch: char) -> bool {
+  ch >= '\x7f'
+}
+
+/// Characters with a named escape are always escaped,
+/// other characters are written in the format `\uXXXX` if `escape_unicode` returns true for them
+fn escape_impl<W: fmt::Write, F: Fn(char) -> bool> (source: &str, dest: &mut W, escape_unicode: F) -> fmt::Result {
+  let mut plain_start = 0;
+
+  for (idx, ch) in source.char_indices() {
+    let mut esc = *b"\\u0000";
+
+    let escaped = match ch {
+      '\\' => "\\\\",
+      // a following digit would make \0 read as an octal escape to other consumers
+      '\0' if source[idx + 1..].starts_with(|c: char| c.is_ascii_digit()) => "\\u0000",
+      '\0' => "\\0",
+      '\x08' => "\\b",
+      '\x0c' => "\\f",
+      '\'' => "\\'",
+      '"' => "\\\"",
+      '\n' => "\\n",
+      '\r' => "\\r",
+      '\t' => "\\t",
+      ch if escape_unicode(ch) => {
         for hex_digit_idx in (0..4).rev() {
           let digit = (((ch as u32) >> (hex_digit_idx * 4)) & 0xf) as u8;
           esc[5 - hex_digit_idx] = if digit < 10 { b'0' + digit } else { b'a' + digit - 10 }
         }
 
-        dest.push_str(unsafe { std::str::from_utf8_unchecked(&esc) });
+        unsafe { std::str::from_utf8_unchecked(&esc) }
       },
-      _ => dest.push(ch)
-    }
+      _ => continue
+    };
+
+    dest.write_str(&source[plain_start..idx])?;
+    dest.write_str(escaped)?;
+
+    plain_start = idx + ch.len_utf8();
   }
+
+  dest.write_str(&source[plain_start..])
+}
+
+
+/// Escape only quotes, backslashes and control characters into their serialization-safe equivalent,
+/// leaving printable unicode characters intact
+/// 
+/// Control characters without a named escape are written in the format `\uXXXX` where `X` are hex digits
+/// 
+/// This version copies onto the end of an existing String
+pub fn escape_str_minimal_into (source: &str, dest: &mut String) {
+  dest.reserve(source.len());
+
+  escape_impl(source, dest, char::is_control).expect("Failed to escape str")
 }
 
 
@@ -117,6 +323,75 @@ mod test {
     assert_eq!(expected, result);
   }
 
+  #[test]
+  fn unescape_modes () {
+    assert_eq!(unescape_str_with_mode(r"a\qb", UnescapeMode::Strict), Err(UnescapeError::UnknownEscape('q')));
+    assert_eq!(unescape_str_with_mode(r"a\qb", UnescapeMode::LenientDrop), Ok("aqb".to_owned()));
+    assert_eq!(unescape_str_with_mode(r"a\qb", UnescapeMode::LenientPreserve), Ok(r"a\qb".to_owned()));
+
+    assert_eq!(unescape_str_with_mode(r"a\", UnescapeMode::Strict), Err(UnescapeError::TrailingBackslash));
+    assert_eq!(unescape_str_with_mode(r"a\", UnescapeMode::LenientDrop), Ok("a".to_owned()));
+    assert_eq!(unescape_str_with_mode(r"a\", UnescapeMode::LenientPreserve), Ok(r"a\".to_owned()));
+
+    assert_eq!(unescape_str_with_mode(r#"\\\"\n"#, UnescapeMode::Strict), Ok("\\\"\n".to_owned()));
+    assert_eq!(unescape_str_with_mode(r"\u00zz", UnescapeMode::LenientPreserve), Err(UnescapeError::InvalidUnicode));
+  }
+
+  #[test]
+  fn null_round_trip () {
+    let source = "a\0b\u{0}12";
+
+    assert_eq!(escape_str(source), r"a\0b\u000012");
+    assert_eq!(unescape_str(&escape_str(source)), source);
+
+    let mut minimal = String::new();
+    escape_str_minimal_into(source, &mut minimal);
+    assert_eq!(minimal, r"a\0b\u000012");
+    assert_eq!(unescape_str(&minimal), source);
+
+    assert_eq!(unescape_str_with_mode(r"\0\07", UnescapeMode::Strict), Ok("\0\u{0}7".to_owned()));
+  }
+
+  #[test]
+  fn bounded () {
+    assert_eq!(unescape_str_bounded(r"a\nb", 3), Ok("a\nb".to_owned()));
+    assert_eq!(unescape_str_bounded(r"a\nbc", 3), Err(UnescapeError::ExceededMaxLength(3)));
+    assert_eq!(unescape_str_bounded(r"\u2764", 2), Err(UnescapeError::ExceededMaxLength(2)));
+    assert_eq!(unescape_str_bounded(r"\u2764", 3), Ok("\u{2764}".to_owned()));
+    assert_eq!(unescape_str_bounded("", 0), Ok(String::new()));
+
+    let long = "x".repeat(10_000);
+    assert_eq!(unescape_str_bounded(&long, 100), Err(UnescapeError::ExceededMaxLength(100)));
+
+    for source in [long.as_str(), &r"\q".repeat(5_000)].iter() {
+      let mut dest = String::with_capacity(100);
+      let cap = dest.capacity();
+
+      assert_eq!(unescape_impl(source, &mut dest, UnescapeMode::LenientPreserve, false, cap), Err(UnescapeError::ExceededMaxLength(cap)));
+      assert_eq!(dest.capacity(), cap);
+      assert!(dest.len() <= cap);
+    }
+  }
+
+  #[test]
+  fn line_continuation () {
+    let source = "first \\\nsecond \\\r\nthird\\n";
+
+    assert_eq!(unescape_str_with_continuations(source, UnescapeMode::Strict), Ok("first second third\n".to_owned()));
+    assert_eq!(unescape_str_with_mode(source, UnescapeMode::LenientDrop), Ok("first \nsecond \r\nthird\n".to_owned()));
+  }
+
+  #[test]
+  fn escape_to_io () {
+    let source = "plain \"quoted\"\n\u{2764} tail";
+
+    let mut bytes: Vec<u8> = Vec::new();
+    escape_str_to_io(source, &mut bytes).unwrap();
+
+    assert_eq!(bytes, escape_str(source).into_bytes());
+    assert_eq!(bytes, br#"plain \"quoted\"\n\u2764 tail"#.to_vec());
+  }
+
   #[test]
   fn escape_ok () {
     let result = escape_str("\\\"\u{2764}");
@@ -9,17 +9,29 @@ pub trait UnwrapPretty {
   /// The type of the inner value yielded by unwrap_pretty and expect_pretty
   type Result;
 
+  /// The type of the error printed by unwrap_pretty and expect_pretty
+  type Error: Display;
+
   /// Unwrap a container type and if there is an error, print it with fmt::Display
   fn unwrap_pretty (self) -> Self::Result;
 
   /// Unwrap a container type and if there is an error, print it and a message using fmt::Display
   fn expect_pretty (self, msg: &str) -> Self::Result;
+
+  /// Unwrap a container type and if there is an error, print it to stderr with fmt::Display
+  /// and return a default value instead of panicking
+  fn unwrap_pretty_or (self, default: Self::Result) -> Self::Result;
+
+  /// Unwrap a container type and if there is an error, print it to stderr with fmt::Display
+  /// and return the result of a callback given the error, instead of panicking
+  fn unwrap_pretty_or_else<F: FnOnce(&Self::Error) -> Self::Result> (self, f: F) -> Self::Result;
 }
 
 impl<R, E> UnwrapPretty for Result<R, E>
 where E: Display
 {
   type Result = R;
+  type Error = E;
 
   fn unwrap_pretty (self) -> Self::Result {
     match self {
@@ -34,4 +46,36 @@ where E: Display
       Err(e) => panic!("{}:\n{}", msg, e)
     }
   }
+
+  fn unwrap_pretty_or (self, default: Self::Result) -> Self::Result {
+    self.unwrap_pretty_or_else(|_| default)
+  }
+
+  fn unwrap_pretty_or_else<F: FnOnce(&Self::Error) -> Self::Result> (self, f: F) -> Self::Result {
+    match self {
+      Ok(r) => r,
+      Err(e) => {
+        eprintln!("Failed to unwrap {}:\n{}", type_name::<Self>(), e);
+        f(&e)
+      }
+    }
+  }
+}
+
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn unwrap_pretty_or () {
+    let ok: Result<i32, String> = Ok(1);
+    let err: Result<i32, String> = Err("bad value".to_owned());
+
+    assert_eq!(ok.clone().unwrap_pretty_or(0), 1);
+    assert_eq!(err.clone().unwrap_pretty_or(0), 0);
+
+    assert_eq!(ok.unwrap_pretty_or_else(|_| unreachable!()), 1);
+    assert_eq!(err.unwrap_pretty_or_else(|e| e.len() as i32), 9);
+  }
 }
\ No newline at end of file
@@ -1,7 +1,10 @@
 //! WrappedArray and support structures
 
 use std::{
-  mem::MaybeUninit,
+  mem::{
+    MaybeUninit,
+    ManuallyDrop,
+  },
   ops::{
     Index,
     IndexMut,
@@ -9,7 +12,19 @@ use std::{
     DerefMut,
   },
   iter::FromIterator,
-  ptr::write,
+  ptr::{
+    read,
+    write,
+    drop_in_place,
+    slice_from_raw_parts_mut,
+  },
+  slice::{
+    Iter as SliceIter,
+    IterMut as SliceIterMut,
+    Windows,
+    from_raw_parts,
+    from_raw_parts_mut,
+  },
 };
 
 /// A const generic wrapper type for doing more by-value operations on Arrays
@@ -20,12 +35,230 @@ impl<T, const N: usize> WrappedArray<T, N> {
   /// # Safety
   /// This is only safe if your array elements are valid when their bytes are all zero
   #[inline] pub unsafe fn zeroed () -> Self { Self(MaybeUninit::zeroed().assume_init()) }
+
+  /// Create a WrappedArray by calling a function with each index in order
+  #[inline]
+  pub fn from_fn<F: FnMut(usize) -> T> (mut f: F) -> Self {
+    let mut out = Filling::new();
+
+    for i in 0..N {
+      out.push(f(i));
+    }
+
+    Self(out.finish())
+  }
   
   /// Get a pointer to the first element of a WrappedArray
   #[inline] pub fn as_ptr (&self) -> *const T { self.0.as_ptr() }
   
   /// Get a mutable pointer to the first element of a WrappedArray
   #[inline] pub fn as_mut_ptr (&mut self) -> *mut T { self.0.as_mut_ptr() }
+
+  /// Get an immutable iterator over the elements of a WrappedArray
+  #[inline] pub fn iter (&self) -> SliceIter<'_, T> { self.0.iter() }
+
+  /// Get a mutable iterator over the elements of a WrappedArray
+  #[inline] pub fn iter_mut (&mut self) -> SliceIterMut<'_, T> { self.0.iter_mut() }
+
+  /// Get an iterator over all overlapping sub-slices of length `size` of a WrappedArray
+  /// 
+  /// # Panics
+  /// Panics if `size` is zero
+  #[inline] pub fn windows (&self, size: usize) -> Windows<'_, T> { self.0.windows(size) }
+
+  /// Get an iterator over each pair of adjacent elements of a WrappedArray
+  #[inline]
+  pub fn pairs (&self) -> impl Iterator<Item = (&T, &T)> {
+    self.0.iter().zip(self.0.iter().skip(1))
+  }
+
+  /// Swap two elements of a WrappedArray
+  /// 
+  /// # Panics
+  /// Panics if either index is out of bounds
+  #[inline] pub fn swap (&mut self, a: usize, b: usize) { self.0.swap(a, b) }
+
+  /// Rotate the elements of a WrappedArray in place, such that the element at `mid` becomes the first
+  /// 
+  /// # Panics
+  /// Panics if `mid` is greater than `N`
+  #[inline] pub fn rotate_left (&mut self, mid: usize) { self.0.rotate_left(mid) }
+
+  /// Rotate the elements of a WrappedArray in place, such that the last `k` elements come first
+  /// 
+  /// # Panics
+  /// Panics if `k` is greater than `N`
+  #[inline] pub fn rotate_right (&mut self, k: usize) { self.0.rotate_right(k) }
+
+  /// Get an immutable view of a WrappedArray as a slice of `N / C` sub-arrays of length `C`
+  /// 
+  /// # Panics
+  /// Panics if `C` is zero or does not evenly divide `N`
+  #[inline]
+  pub fn as_chunks<const C: usize> (&self) -> &[[T; C]] {
+    assert!(C != 0 && N / C * C == N, "WrappedArray::as_chunks chunk size must evenly divide the array length");
+
+    unsafe { from_raw_parts(self.as_ptr() as *const [T; C], N / C) }
+  }
+
+  /// Get a mutable view of a WrappedArray as a slice of `N / C` sub-arrays of length `C`
+  /// 
+  /// # Panics
+  /// Panics if `C` is zero or does not evenly divide `N`
+  #[inline]
+  pub fn as_chunks_mut<const C: usize> (&mut self) -> &mut [[T; C]] {
+    assert!(C != 0 && N / C * C == N, "WrappedArray::as_chunks_mut chunk size must evenly divide the array length");
+
+    unsafe { from_raw_parts_mut(self.as_mut_ptr() as *mut [T; C], N / C) }
+  }
+
+  /// Consume a WrappedArray, creating a new one by applying a function to each element in order
+  /// 
+  /// Unlike `into_iter`, this does not require `T: Copy`
+  #[inline]
+  pub fn map<U, F: FnMut(T) -> U> (self, mut f: F) -> WrappedArray<U, N> {
+    let mut rem = Remaining::new(self);
+    let mut out = Filling::new();
+
+    while let Some(e) = rem.next() {
+      out.push(f(e));
+    }
+
+    WrappedArray(out.finish())
+  }
+
+  /// Consume two WrappedArrays, moving their elements in order into a new WrappedArray
+  /// 
+  /// The length of the result cannot be expressed as `N + M` without generic const expressions,
+  /// so it is given as a separate parameter, usually inferred from the binding
+  /// 
+  /// # Panics
+  /// Panics if `O` is not `N + M`
+  #[inline]
+  pub fn concat<const M: usize, const O: usize> (self, other: WrappedArray<T, M>) -> WrappedArray<T, O> {
+    assert!(O == N + M, "WrappedArray::concat result length must be the sum of the input lengths");
+
+    let mut first = Remaining::new(self);
+    let mut second = Remaining::new(other);
+    let mut out = Filling::new();
+
+    while let Some(e) = first.next() {
+      out.push(e);
+    }
+
+    while let Some(e) = second.next() {
+      out.push(e);
+    }
+
+    WrappedArray(out.finish())
+  }
+
+  /// Consume a WrappedArray, moving each element into an accumulator in order
+  /// 
+  /// Unlike `into_iter`, this does not require `T: Copy`
+  #[inline]
+  pub fn fold<B, F: FnMut(B, T) -> B> (self, init: B, mut f: F) -> B {
+    let mut rem = Remaining::new(self);
+    let mut acc = init;
+
+    while let Some(e) = rem.next() {
+      acc = f(acc, e);
+    }
+
+    acc
+  }
+
+  /// Consume a WrappedArray, moving each element into an accumulator in order,
+  /// where the accumulator is initialized with the first element
+  /// 
+  /// Unlike `into_iter`, this does not require `T: Copy`
+  /// 
+  /// # Panics
+  /// Panics if `N` is zero
+  #[inline]
+  pub fn reduce<F: Fn(T, T) -> T> (self, f: F) -> T {
+    let mut rem = Remaining::new(self);
+    let mut acc = rem.next().expect("WrappedArray::reduce called on an empty array");
+
+    while let Some(e) = rem.next() {
+      acc = f(acc, e);
+    }
+
+    acc
+  }
+}
+
+
+/// Moves elements out of a WrappedArray one at a time,
+/// dropping any not yet taken if it is dropped itself (e.g. during a panic)
+struct Remaining<T, const N: usize> {
+  arr: ManuallyDrop<[T; N]>,
+  idx: usize,
+}
+
+impl<T, const N: usize> Remaining<T, N> {
+  #[inline]
+  fn new (arr: WrappedArray<T, N>) -> Self {
+    Self { arr: ManuallyDrop::new(arr.0), idx: 0 }
+  }
+
+  #[inline]
+  fn next (&mut self) -> Option<T> {
+    if self.idx < N {
+      let el = unsafe { read(self.arr.as_ptr().add(self.idx)) };
+
+      self.idx += 1;
+
+      Some(el)
+    } else {
+      None
+    }
+  }
+}
+
+impl<T, const N: usize> Drop for Remaining<T, N> {
+  fn drop (&mut self) {
+    unsafe { drop_in_place(&mut self.arr[self.idx..] as *mut [T]) }
+  }
+}
+
+
+/// Moves elements into an uninitialized array one at a time,
+/// dropping any already written if it is dropped before being filled (e.g. during a panic)
+struct Filling<T, const N: usize> {
+  arr: MaybeUninit<[T; N]>,
+  len: usize,
+}
+
+impl<T, const N: usize> Filling<T, N> {
+  #[inline]
+  fn new () -> Self {
+    Self { arr: MaybeUninit::uninit(), len: 0 }
+  }
+
+  #[inline]
+  fn push (&mut self, e: T) {
+    assert!(self.len < N, "WrappedArray overflow");
+
+    unsafe { write((self.arr.as_mut_ptr() as *mut T).add(self.len), e) };
+
+    self.len += 1;
+  }
+
+  #[inline]
+  fn finish (self) -> [T; N] {
+    assert!(self.len == N, "WrappedArray underflow");
+
+    let filled = ManuallyDrop::new(self);
+
+    unsafe { read(filled.arr.as_ptr()) }
+  }
+}
+
+impl<T, const N: usize> Drop for Filling<T, N> {
+  fn drop (&mut self) {
+    unsafe { drop_in_place(slice_from_raw_parts_mut(self.arr.as_mut_ptr() as *mut T, self.len)) }
+  }
 }
 
 
@@ -100,19 +333,160 @@ impl<T: Copy, const N: usize> IntoIterator for WrappedArray<T, N> {
 impl<T, const N: usize> FromIterator<T> for WrappedArray<T, N> {
   #[inline]
   fn from_iter<I: IntoIterator<Item = T>> (iter: I) -> Self {
-    let mut res = MaybeUninit::uninit();
-    let ptr = res.as_mut_ptr() as *mut T;
-    
-    let mut i = 0usize;
+    let mut out = Filling::new();
 
     for e in iter {
-      assert!(i < N, "WrappedArray FromIterator overflow");
-      unsafe { write(ptr.add(i), e) };
-      i += 1;
+      assert!(out.len < N, "WrappedArray FromIterator overflow");
+      out.push(e);
+    }
+
+    assert!(out.len == N, "WrappedArray FromIterator underflow");
+
+    Self(out.finish())
+  }
+}
+
+
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn iter () {
+    let mut arr = WrappedArray([1, 2, 3, 4]);
+
+    assert_eq!(arr.iter().sum::<i32>(), 10);
+
+    for e in arr.iter_mut() {
+      *e *= 2;
+    }
+
+    assert_eq!(arr.iter().sum::<i32>(), 20);
+  }
+
+  #[test]
+  fn windows_pairs () {
+    let arr = WrappedArray([1, 4, 9, 16, 25]);
+
+    let diffs: Vec<i32> = arr.pairs().map(|(a, b)| b - a).collect();
+    assert_eq!(diffs, vec![3, 5, 7, 9]);
+
+    let window_diffs: Vec<i32> = arr.windows(2).map(|w| w[1] - w[0]).collect();
+    assert_eq!(window_diffs, diffs);
+
+    assert_eq!(arr.windows(3).count(), 3);
+    assert_eq!(WrappedArray([1]).pairs().count(), 0);
+  }
+
+  #[test]
+  fn as_chunks () {
+    let mut arr: WrappedArray<f32, 16> = (0..16).map(|i| i as f32).collect();
+
+    let chunks = arr.as_chunks::<4>();
+    assert_eq!(chunks.len(), 4);
+    assert_eq!(chunks[1], [4.0, 5.0, 6.0, 7.0]);
+
+    for chunk in arr.as_chunks_mut::<4>() {
+      chunk[0] = -1.0;
     }
 
-    assert!(i == N, "WrappedArray FromIterator underflow");
+    assert_eq!(arr[0], -1.0);
+    assert_eq!(arr[4], -1.0);
+    assert_eq!(arr[5], 5.0);
+    assert_eq!(arr[12], -1.0);
+  }
+
+  #[test]
+  fn swap_rotate () {
+    let mut arr = WrappedArray([1, 2, 3, 4]);
+
+    arr.rotate_left(1);
+    assert_eq!(arr.0, [2, 3, 4, 1]);
+
+    arr.rotate_right(2);
+    assert_eq!(arr.0, [4, 1, 2, 3]);
+
+    arr.swap(0, 3);
+    assert_eq!(arr.0, [3, 1, 2, 4]);
+  }
+
+  #[test]
+  fn fold_reduce () {
+    let arr = WrappedArray([3, 9, 2, 7]);
+
+    assert_eq!(WrappedArray([1, 2, 3, 4]).fold(0, |acc, e| acc + e), 10);
+    assert_eq!(arr.reduce(|a, b| if b > a { b } else { a }), 9);
+
+    let strings = WrappedArray(["a".to_owned(), "b".to_owned(), "c".to_owned()]);
+
+    assert_eq!(strings.fold(String::new(), |acc, e| acc + &e), "abc");
+  }
+
+  #[test]
+  fn map_from_fn () {
+    let arr = WrappedArray::<usize, 4>::from_fn(|i| i * i);
+    assert_eq!(arr.0, [0, 1, 4, 9]);
+
+    let strings = arr.map(|e| e.to_string());
+    assert_eq!(strings.0, ["0", "1", "4", "9"]);
+  }
+
+  #[test]
+  fn concat () {
+    let joined: WrappedArray<String, 5> = WrappedArray(["a".to_owned(), "b".to_owned()]).concat(WrappedArray(["c".to_owned(), "d".to_owned(), "e".to_owned()]));
+
+    assert_eq!(joined.0, ["a", "b", "c", "d", "e"]);
+
+    let empty: WrappedArray<i32, 0> = WrappedArray([]);
+    let same: WrappedArray<i32, 2> = empty.concat(WrappedArray([1, 2]));
+    assert_eq!(same.0, [1, 2]);
+  }
+
+  #[test]
+  #[should_panic(expected = "sum of the input lengths")]
+  fn concat_wrong_length () {
+    let _: WrappedArray<i32, 4> = WrappedArray([1, 2]).concat(WrappedArray([3]));
+  }
+
+  #[test]
+  fn zero_length () {
+    let empty: WrappedArray<String, 0> = WrappedArray::from_fn(|_| unreachable!());
+    let mapped: WrappedArray<usize, 0> = empty.map(|_| unreachable!());
+
+    assert_eq!(mapped.iter().count(), 0);
+    assert_eq!(mapped.into_iter().count(), 0);
+
+    let collected: WrappedArray<String, 0> = std::iter::empty().collect();
+    assert_eq!(collected.fold(0, |acc, _| acc + 1), 0);
+
+    let zeroed: WrappedArray<u64, 0> = unsafe { WrappedArray::zeroed() };
+    assert_eq!(zeroed.into_iter().next(), None);
+  }
+
+  #[test]
+  #[should_panic(expected = "overflow")]
+  fn zero_length_overflow () {
+    let _: WrappedArray<u8, 0> = std::iter::once(1).collect();
+  }
+
+  #[test]
+  fn from_fn_panic_drops_written () {
+    use std::{ rc::Rc, panic::{ catch_unwind, AssertUnwindSafe } };
+
+    let counter = Rc::new(());
+
+    let res = catch_unwind(AssertUnwindSafe(|| {
+      WrappedArray::<Rc<()>, 4>::from_fn(|i| if i < 2 { counter.clone() } else { panic!() })
+    }));
+
+    assert!(res.is_err());
+    assert_eq!(Rc::strong_count(&counter), 1);
+  }
 
-    unsafe { res.assume_init() }
+  #[test]
+  #[should_panic]
+  fn as_chunks_uneven () {
+    WrappedArray([0u8; 10]).as_chunks::<4>();
   }
 }
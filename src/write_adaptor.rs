@@ -14,6 +14,7 @@ use std::{ fmt, io };
 /// + Adapting an adaptor would cause all sorts of problems
 pub struct FMTAdaptor<'a>{
   io_writer: &'a mut dyn io::Write,
+  bytes_written: usize,
   /// If an io error occurs while using a FMTAdaptor,
   /// it will be stored here and block further usage until it is resolved
   pub error: Option<io::Error>
@@ -25,6 +26,10 @@ pub struct FMTAdaptor<'a>{
 pub trait FMTAdaptable {
   /// Create a FMTAdaptor for an io::Write
   fn adapt_to_fmt (&mut self) -> FMTAdaptor;
+
+  /// Create a FMTAdaptorGen for an io::Write
+  fn adapt_to_fmt_gen (&mut self) -> FMTAdaptorGen<'_, Self>
+  where Self: io::Write + Sized;
 }
 
 impl<T> FMTAdaptable for T
@@ -32,7 +37,34 @@ where T: io::Write
 {
   #[inline]
   fn adapt_to_fmt (&mut self) -> FMTAdaptor<'_> {
-    FMTAdaptor { io_writer: self, error: None }
+    FMTAdaptor { io_writer: self, bytes_written: 0, error: None }
+  }
+
+  #[inline]
+  fn adapt_to_fmt_gen (&mut self) -> FMTAdaptorGen<'_, Self> {
+    FMTAdaptorGen { io_writer: self, bytes_written: 0, error: None }
+  }
+}
+
+impl<'a> FMTAdaptor<'a> {
+  /// Take the stored io error out of a FMTAdaptor, if there is one,
+  /// allowing further writes to resume
+  #[inline]
+  pub fn take_error (&mut self) -> Option<io::Error> {
+    self.error.take()
+  }
+
+  /// Discard the stored io error of a FMTAdaptor, if there is one,
+  /// allowing further writes to resume
+  #[inline]
+  pub fn clear_error (&mut self) {
+    self.error = None;
+  }
+
+  /// Get the number of bytes successfully written to the inner io::Write through a FMTAdaptor
+  #[inline]
+  pub fn bytes_written (&self) -> usize {
+    self.bytes_written
   }
 }
 
@@ -41,7 +73,66 @@ impl<'a> fmt::Write for FMTAdaptor<'a> {
   fn write_str (&mut self, s: &str) -> fmt::Result {
     if self.error.is_none() {
       match self.io_writer.write_all(s.as_bytes()) {
-        Ok(()) => Ok(()),
+        Ok(()) => {
+          self.bytes_written += s.len();
+          Ok(())
+        }
+        Err(e) => {
+          self.error = Some(e);
+          Err(fmt::Error)
+        }
+      }
+    } else {
+      Err(fmt::Error)
+    }
+  }
+}
+
+
+
+/// A generic version of FMTAdaptor,
+/// which avoids dynamic dispatch on the inner io::Write so that writes can be inlined
+/// 
+/// See FMTAdaptor for more details
+pub struct FMTAdaptorGen<'a, W: io::Write> {
+  io_writer: &'a mut W,
+  bytes_written: usize,
+  /// If an io error occurs while using a FMTAdaptorGen,
+  /// it will be stored here and block further usage until it is resolved
+  pub error: Option<io::Error>
+}
+
+impl<'a, W: io::Write> FMTAdaptorGen<'a, W> {
+  /// Take the stored io error out of a FMTAdaptorGen, if there is one,
+  /// allowing further writes to resume
+  #[inline]
+  pub fn take_error (&mut self) -> Option<io::Error> {
+    self.error.take()
+  }
+
+  /// Discard the stored io error of a FMTAdaptorGen, if there is one,
+  /// allowing further writes to resume
+  #[inline]
+  pub fn clear_error (&mut self) {
+    self.error = None;
+  }
+
+  /// Get the number of bytes successfully written to the inner io::Write through a FMTAdaptorGen
+  #[inline]
+  pub fn bytes_written (&self) -> usize {
+    self.bytes_written
+  }
+}
+
+impl<'a, W: io::Write> fmt::Write for FMTAdaptorGen<'a, W> {
+  #[inline]
+  fn write_str (&mut self, s: &str) -> fmt::Result {
+    if self.error.is_none() {
+      match self.io_writer.write_all(s.as_bytes()) {
+        Ok(()) => {
+          self.bytes_written += s.len();
+          Ok(())
+        }
         Err(e) => {
           self.error = Some(e);
           Err(fmt::Error)
@@ -94,4 +185,103 @@ impl<'a> io::Write for IOAdaptor<'a> {
 
   #[inline]
   fn flush (&mut self) -> io::Result<()> { Ok(()) }
+}
+
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::fmt::Write;
+
+  /// An io::Write which fails a given number of times before writing to a Vec
+  struct FlakyWriter {
+    failures: usize,
+    data: Vec<u8>,
+  }
+
+  impl io::Write for FlakyWriter {
+    fn write (&mut self, buf: &[u8]) -> io::Result<usize> {
+      if self.failures > 0 {
+        self.failures -= 1;
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "flaky"))
+      } else {
+        self.data.write(buf)
+      }
+    }
+
+    fn flush (&mut self) -> io::Result<()> { Ok(()) }
+  }
+
+  #[test]
+  fn take_and_clear_error () {
+    let mut writer = FlakyWriter { failures: 2, data: Vec::new() };
+
+    {
+      let mut adaptor = writer.adapt_to_fmt();
+
+      assert!(write!(adaptor, "a").is_err());
+      assert!(write!(adaptor, "b").is_err());
+
+      let e = adaptor.take_error().expect("Expected an io error");
+      assert_eq!(e.kind(), io::ErrorKind::BrokenPipe);
+      assert!(adaptor.error.is_none());
+
+      assert!(write!(adaptor, "c").is_err());
+      adaptor.clear_error();
+
+      assert!(write!(adaptor, "d").is_ok());
+      assert!(adaptor.take_error().is_none());
+    }
+
+    assert_eq!(writer.data, b"d");
+  }
+
+  #[test]
+  fn bytes_written () {
+    let mut writer = FlakyWriter { failures: 1, data: Vec::new() };
+
+    {
+      let mut adaptor = writer.adapt_to_fmt();
+
+      assert!(write!(adaptor, "lost").is_err());
+      assert_eq!(adaptor.bytes_written(), 0);
+      adaptor.clear_error();
+
+      write!(adaptor, "abc").unwrap();
+      write!(adaptor, "{}", 12345).unwrap();
+      write!(adaptor, "\u{2764}").unwrap();
+
+      assert_eq!(adaptor.bytes_written(), 11);
+    }
+
+    assert_eq!(writer.data.len(), 11);
+
+    let mut buf: Vec<u8> = Vec::new();
+    let mut adaptor = buf.adapt_to_fmt_gen();
+
+    write!(adaptor, "{}-{}", 12, 34).unwrap();
+    assert_eq!(adaptor.bytes_written(), 5);
+  }
+
+  #[test]
+  fn generic_adaptor () {
+    let mut buf: Vec<u8> = Vec::new();
+
+    {
+      let mut adaptor = buf.adapt_to_fmt_gen();
+
+      write!(adaptor, "{} + {} = {}", 1, 2, 3).unwrap();
+      assert!(adaptor.take_error().is_none());
+    }
+
+    assert_eq!(buf, b"1 + 2 = 3");
+
+    let mut writer = FlakyWriter { failures: 1, data: Vec::new() };
+    let mut adaptor = writer.adapt_to_fmt_gen();
+
+    assert!(write!(adaptor, "a").is_err());
+    assert!(write!(adaptor, "b").is_err());
+    adaptor.clear_error();
+    assert!(write!(adaptor, "c").is_ok());
+  }
 }
\ No newline at end of file